@@ -0,0 +1,245 @@
+/* project use */
+use crate::graph;
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* std use */
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub tig: String,
+    pub begin: u64,
+    pub end: u64,
+    pub position: u64,
+    pub left: u64,
+    pub right: u64,
+    pub shared: u64,
+    pub score: f64,
+}
+
+/* Barcodes with a molecule in window before and after each position multiple of step, positions without full windows are ignored */
+pub fn scan(tig_len: u64, barcode2premol2pos: &HashMap<String, Vec<(u64, u64)>>, window: u64, step: u64) -> Vec<(u64, u64, u64, u64)> {
+    let nb_position = (tig_len / step + 1) as usize;
+    let mut left = vec![0i64; nb_position + 1];
+    let mut right = vec![0i64; nb_position + 1];
+    let mut shared = vec![0i64; nb_position + 1];
+
+    for poss in barcode2premol2pos.values() {
+        // a molecule (b, e) is in left window of p if b < p < e + window, and in right window if b - window < p < e
+        let in_left = merge(poss.iter().map(|(b, e)| (*b, e + window)).collect());
+        let in_right = merge(poss.iter().map(|(b, e)| (b.saturating_sub(window), *e)).collect());
+        let in_both = intersect(&in_left, &in_right);
+
+        for (intervals, diff) in &mut [(in_left, &mut left), (in_right, &mut right), (in_both, &mut shared)] {
+            for (b, e) in intervals.iter() {
+                let first = (b / step + 1) as usize;
+                let last = (e.div_ceil(step) as usize).min(nb_position);
+                if first < last {
+                    diff[first] += 1;
+                    diff[last] -= 1;
+                }
+            }
+        }
+    }
+
+    let (mut nb_left, mut nb_right, mut nb_shared) = (0, 0, 0);
+    let mut counts = Vec::new();
+    for i in 0..nb_position {
+        nb_left += left[i];
+        nb_right += right[i];
+        nb_shared += shared[i];
+
+        let position = i as u64 * step;
+        if position >= window && position + window <= tig_len {
+            counts.push((position, nb_left as u64, nb_right as u64, nb_shared as u64));
+        }
+    }
+
+    counts
+}
+
+/* Consecutive positions where few barcodes of smallest side are shared with other side are merge in one breakpoint, position with the lowest sharing is keep */
+pub fn detect(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2len: &HashMap<String, usize>, window: u64, step: u64, min_barcodes: u64, max_shared: f64) -> Vec<Breakpoint> {
+    let mut tigs: Vec<&String> = tig2barcode2premol2pos.keys().collect();
+    tigs.sort();
+
+    let mut breakpoints = Vec::new();
+    for tig in tigs {
+        let barcode2premol2pos = &tig2barcode2premol2pos[tig];
+        let tig_len = graph::tig_length(tig, barcode2premol2pos, tig2len);
+
+        let mut current: Option<Breakpoint> = None;
+        for (position, left, right, shared) in scan(tig_len, barcode2premol2pos, window, step) {
+            let smallest = left.min(right);
+            let score = if smallest == 0 { 0.0 } else { 1.0 - shared as f64 / smallest as f64 };
+
+            if smallest < min_barcodes || 1.0 - score > max_shared {
+                breakpoints.extend(current.take());
+                continue;
+            }
+
+            let candidate = Breakpoint {
+                tig: tig.clone(),
+                begin: position,
+                end: position + 1,
+                position,
+                left,
+                right,
+                shared,
+                score,
+            };
+
+            current = match current.take() {
+                Some(mut breakpoint) if breakpoint.end + step > position => {
+                    breakpoint.end = position + 1;
+                    if candidate.score > breakpoint.score {
+                        breakpoint = Breakpoint { begin: breakpoint.begin, ..candidate };
+                    }
+                    Some(breakpoint)
+                }
+                previous => {
+                    breakpoints.extend(previous);
+                    Some(candidate)
+                }
+            };
+        }
+        breakpoints.extend(current);
+    }
+
+    breakpoints
+}
+
+/* Bed with position of lowest sharing as name, score scale between 0 and 1000, and number of barcodes left, right and shared */
+pub fn write_bed<W: Write>(writer: &mut W, breakpoints: &[Breakpoint]) -> std::io::Result<()> {
+    for breakpoint in breakpoints {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            breakpoint.tig,
+            breakpoint.begin,
+            breakpoint.end,
+            breakpoint.position,
+            (breakpoint.score * 1000.0).round() as u64,
+            breakpoint.left,
+            breakpoint.right,
+            breakpoint.shared
+        )?;
+    }
+
+    Ok(())
+}
+
+/* Split contigs at breakpoint position, parts are named contig_1, contig_2, ... and unbroken contigs keep their name */
+pub fn split<R: Read, W: Write>(reader: R, writer: W, breakpoints: &[Breakpoint]) -> std::io::Result<()> {
+    let mut tig2positions: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for breakpoint in breakpoints {
+        tig2positions.entry(&breakpoint.tig).or_default().push(breakpoint.position as usize);
+    }
+
+    let mut fasta = bio::io::fasta::Writer::new(writer);
+    for record in bio::io::fasta::Reader::new(reader).records() {
+        let record = record?;
+
+        match tig2positions.get(record.id()) {
+            None => fasta.write_record(&record)?,
+            Some(positions) => {
+                let mut begin = 0;
+                for (i, end) in positions.iter().chain(std::iter::once(&record.seq().len())).enumerate() {
+                    let end = (*end).min(record.seq().len());
+                    fasta.write(&format!("{}_{}", record.id(), i + 1), None, &record.seq()[begin..end])?;
+                    begin = end;
+                }
+            }
+        }
+    }
+
+    fasta.flush()
+}
+
+fn merge(mut intervals: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    intervals.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(intervals.len());
+    for (begin, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if begin <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((begin, end)),
+        }
+    }
+
+    merged
+}
+
+fn intersect(intervals1: &[(u64, u64)], intervals2: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut intersection = Vec::new();
+
+    let (mut i, mut j) = (0, 0);
+    while i < intervals1.len() && j < intervals2.len() {
+        let begin = intervals1[i].0.max(intervals2[j].0);
+        let end = intervals1[i].1.min(intervals2[j].1);
+        if begin < end {
+            intersection.push((begin, end));
+        }
+
+        if intervals1[i].1 < intervals2[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    intersection
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    /* barcodes cover tig1 on each side of 50000 but no molecule cross it */
+    fn misjoin() -> Tig2Barcode2Premol2Pos {
+        let mut tig2barcode = Tig2Barcode2Premol2Pos::new();
+        let barcode2premol = tig2barcode.entry("tig1".to_string()).or_default();
+        for b in 0..10 {
+            barcode2premol.insert(format!("L{}", b), vec![(b * 3000, 50000)]);
+            barcode2premol.insert(format!("R{}", b), vec![(50000, 70000 + b * 3000)]);
+            barcode2premol.insert(format!("S{}", b), vec![(b * 10000, b * 10000 + 9000)]);
+        }
+
+        tig2barcode
+    }
+
+    #[test]
+    fn misjoin_position() {
+        let mut tig2len = HashMap::new();
+        tig2len.insert("tig1".to_string(), 100000);
+
+        let breakpoints = detect(&misjoin(), &tig2len, 10000, 1000, 5, 0.2);
+
+        assert_eq!(breakpoints.len(), 1);
+        assert_eq!(breakpoints[0].position, 50000);
+        assert_eq!(breakpoints[0].shared, 0);
+        assert_eq!(breakpoints[0].score, 1.0);
+    }
+
+    #[test]
+    fn split_fasta() {
+        let fasta: &[u8] = b">tig1\nAAAACCCC\n>tig2\nGGGG\n";
+        let breakpoint = Breakpoint {
+            tig: "tig1".to_string(),
+            begin: 4,
+            end: 5,
+            position: 4,
+            left: 10,
+            right: 10,
+            shared: 0,
+            score: 1.0,
+        };
+
+        let mut output = Vec::new();
+        split(fasta, &mut output, &[breakpoint]).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), ">tig1_1\nAAAA\n>tig1_2\nCCCC\n>tig2\nGGGG\n");
+    }
+}
@@ -0,0 +1,208 @@
+/* project use */
+use crate::cli;
+use crate::contig::Tig2Summary;
+use crate::gexf;
+use crate::graph::Graph;
+use crate::haplotype;
+use crate::provenance::Provenance;
+
+/* crates use */
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/* std use */
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+/* Graphs of a run and their contig summary, with their haplotype in split mode */
+pub type Graphs = Vec<(Option<u8>, Graph, Tig2Summary)>;
+
+/* Part of provenance a checkpoint depends on, outputs and log options aren't in provenance, config content is already in parameters */
+fn stage_key(provenance: &Provenance) -> Provenance {
+    let config = provenance.parameters.get("config").cloned().unwrap_or_default();
+
+    Provenance {
+        version: provenance.version.clone(),
+        command: provenance.command.clone(),
+        parameters: provenance.parameters.iter().filter(|(name, _)| *name != "config" && !cli::Build::POST_PROCESSING.contains(&name.as_str())).map(|(name, values)| (name.clone(), values.clone())).collect(),
+        inputs: provenance.inputs.iter().filter(|(path, _)| !config.contains(path)).map(|(path, input)| (path.clone(), input.clone())).collect(),
+    }
+}
+
+/* Inputs are identify by their checksum, a run without checksum of its inputs can't be resume */
+fn is_identified(provenance: &Provenance) -> bool {
+    provenance.inputs.values().all(|input| input.md5.is_some())
+}
+
+/* Completed stages of a run, stages are valid only for the same version, stage parameters and inputs */
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    provenance: Provenance,
+    molecules: bool,
+    graphs: Option<Vec<Option<u8>>>,
+}
+
+pub struct Checkpoint {
+    dir: PathBuf,
+    state: State,
+}
+
+impl Checkpoint {
+    /* A checkpoint of another run is drop */
+    pub fn open(dir: &str, provenance: &Provenance) -> Self {
+        let dir = PathBuf::from(dir);
+        std::fs::create_dir_all(&dir).unwrap_or_else(|e| panic!("Can't create checkpoint directory {}: {}", dir.display(), e));
+
+        let previous: Option<State> = std::fs::File::open(dir.join("state.json")).ok().and_then(|f| serde_json::from_reader(BufReader::new(f)).ok());
+
+        let checkpoint = match previous {
+            Some(state) if is_identified(&state.provenance) && stage_key(&state.provenance) == stage_key(provenance) => {
+                log::info!("resume from checkpoint {}, molecules: {}, graphs: {}", dir.display(), state.molecules, state.graphs.is_some());
+                Checkpoint { dir, state }
+            }
+            _ => Checkpoint {
+                dir,
+                state: State {
+                    provenance: provenance.clone(),
+                    ..Default::default()
+                },
+            },
+        };
+        checkpoint.write("state.json", |w| serde_json::to_writer(w, &checkpoint.state).map_err(std::io::Error::from));
+
+        checkpoint
+    }
+
+    pub fn molecules<T: DeserializeOwned>(&self) -> Option<T> {
+        if !self.state.molecules {
+            return None;
+        }
+
+        let file = std::fs::File::open(self.dir.join("molecules.json")).ok()?;
+        serde_json::from_reader(BufReader::new(file)).ok()
+    }
+
+    pub fn save_molecules<T: Serialize>(&mut self, molecules: &T) {
+        self.write("molecules.json", |w| serde_json::to_writer(w, molecules).map_err(std::io::Error::from));
+
+        self.state.molecules = true;
+        self.write("state.json", |w| serde_json::to_writer(w, &self.state).map_err(std::io::Error::from));
+    }
+
+    /* Graphs are store in gexf, one by haplotype, with their contig summary after graph construction */
+    pub fn graphs(&self) -> Option<Graphs> {
+        let haps = self.state.graphs.as_ref()?;
+
+        let file = std::fs::File::open(self.dir.join("contigs.json")).ok()?;
+        let summaries: Vec<Tig2Summary> = serde_json::from_reader(BufReader::new(file)).ok()?;
+        if summaries.len() != haps.len() {
+            return None;
+        }
+
+        let mut graphs = Vec::new();
+        for (hp, tig2summary) in haps.iter().zip(summaries) {
+            let file = std::fs::File::open(self.dir.join(graph_name(*hp))).ok()?;
+            graphs.push((*hp, gexf::read(BufReader::new(file)).ok()?, tig2summary));
+        }
+
+        Some(graphs)
+    }
+
+    pub fn save_graphs(&mut self, graphs: &Graphs) {
+        for (hp, graph, _) in graphs {
+            self.write(&graph_name(*hp), |w| gexf::write(w, graph, None));
+        }
+        let summaries: Vec<&Tig2Summary> = graphs.iter().map(|(_, _, s)| s).collect();
+        self.write("contigs.json", |w| serde_json::to_writer(w, &summaries).map_err(std::io::Error::from));
+
+        self.state.graphs = Some(graphs.iter().map(|(hp, _, _)| *hp).collect());
+        self.write("state.json", |w| serde_json::to_writer(w, &self.state).map_err(std::io::Error::from));
+    }
+
+    /* Write in a temporary file renamed at end, an interrupted write never replace a complete file */
+    fn write<F: FnOnce(&mut BufWriter<std::fs::File>) -> std::io::Result<()>>(&self, name: &str, content: F) {
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!("{}.tmp", name));
+
+        let mut writer = BufWriter::new(std::fs::File::create(&tmp).unwrap_or_else(|e| panic!("Can't create checkpoint file {}: {}", tmp.display(), e)));
+        content(&mut writer).expect("error durring checkpoint write");
+        writer.into_inner().map_err(|e| e.into_error()).expect("error durring checkpoint write");
+
+        std::fs::rename(&tmp, &path).unwrap_or_else(|e| panic!("Can't rename checkpoint file {}: {}", tmp.display(), e));
+    }
+}
+
+fn graph_name(hp: Option<u8>) -> String {
+    match hp {
+        Some(hp) => haplotype::path("graph.gexf", hp),
+        None => "graph.gexf".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::{key, Edge, Node};
+    use crate::provenance::Input;
+
+    #[test]
+    fn resume() {
+        let dir = std::env::temp_dir().join(format!("mapping2barcodegraph-checkpoint-{}", std::process::id()));
+        let dir = dir.to_string_lossy().to_string();
+        let provenance = Provenance {
+            command: "build".to_string(),
+            ..Default::default()
+        };
+
+        let mut graph = Graph::default();
+        graph.nodes.insert("A".to_string(), Node { molecules: 2, ..Default::default() });
+        graph.nodes.insert("B".to_string(), Node::default());
+        graph.edges.insert(key("A", "B"), Edge { pairs: 3, weight: 3.0, ..Default::default() });
+
+        let mut checkpoint = Checkpoint::open(&dir, &provenance);
+        assert_eq!(checkpoint.molecules::<Vec<u64>>(), None);
+        checkpoint.save_molecules(&vec![1u64, 2, 3]);
+        checkpoint.save_graphs(&vec![(Some(1), graph.clone(), Tig2Summary::new())]);
+
+        let checkpoint = Checkpoint::open(&dir, &provenance);
+        assert_eq!(checkpoint.molecules::<Vec<u64>>(), Some(vec![1, 2, 3]));
+        let graphs = checkpoint.graphs().unwrap();
+        assert_eq!(graphs[0].0, Some(1));
+        assert_eq!(graphs[0].1.edges[&key("A", "B")].pairs, 3);
+        assert_eq!(graphs[0].1.nodes["A"].molecules, 2);
+
+        // another parameter set restart from begin
+        let other = Provenance {
+            command: "build".to_string(),
+            version: "other".to_string(),
+            ..Default::default()
+        };
+        let checkpoint = Checkpoint::open(&dir, &other);
+        assert_eq!(checkpoint.molecules::<Vec<u64>>(), None);
+        assert!(checkpoint.graphs().is_none());
+
+        // post processing keep checkpoint, any other parameter or input content doesn't
+        let mut pruned = other.clone();
+        pruned.parameters.insert("prune".to_string(), vec!["isolated".to_string()]);
+        pruned.parameters.insert("config".to_string(), vec!["other.toml".to_string()]);
+        assert_eq!(stage_key(&pruned), stage_key(&other));
+        pruned.parameters.insert("weight-model".to_string(), vec!["pairs".to_string()]);
+        assert_ne!(stage_key(&pruned), stage_key(&other));
+        let mut added = other.clone();
+        added.parameters.insert("new-option".to_string(), vec!["true".to_string()]);
+        assert_ne!(stage_key(&added), stage_key(&other));
+
+        let mut input = other.clone();
+        input.inputs.insert("ema.tsv".to_string(), Input { size: Some(10), md5: Some("aaa".to_string()) });
+        let mut changed = input.clone();
+        changed.inputs.insert("ema.tsv".to_string(), Input { size: Some(10), md5: Some("bbb".to_string()) });
+        assert_ne!(stage_key(&input), stage_key(&changed));
+        assert!(is_identified(&input));
+        changed.inputs.insert("ema.tsv".to_string(), Input { size: Some(10), md5: None });
+        assert!(!is_identified(&changed));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,527 @@
+/* project use */
+use crate::community;
+use crate::contig;
+use crate::file;
+use crate::graph;
+use crate::haplotype;
+use crate::logging;
+use crate::mask;
+use crate::merge;
+use crate::prune;
+use crate::query;
+use crate::scaffold;
+use crate::simulate;
+
+/* crates use */
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mapping2barcode", about = "Use mapping of barcode 10x read to assembly to build a barcode graph", author = "Pierre Marijon <pmarijon@mpi-inf.mpg.de>")]
+pub struct Command {
+    #[structopt(short = "c", long = "config", help = "toml or yaml (.yaml or .yml) file, global options at top level and a table by subcommand, keys are long options, command line options override them")]
+    pub config: Option<String>,
+
+    #[structopt(short = "v", long = "verbose", help = "more log messages, can be repeat", global = true, parse(from_occurrences))]
+    pub verbose: u8,
+
+    #[structopt(short = "q", long = "quiet", help = "less log messages, can be repeat, errors are always report", global = true, parse(from_occurrences))]
+    pub quiet: u8,
+
+    #[structopt(long = "log-format", help = "format of log messages write on standard error", default_value = "text", global = true, possible_values = logging::Format::NAMES)]
+    pub log_format: logging::Format,
+
+    #[structopt(subcommand)]
+    pub subcommand: SubCommand,
+}
+
+/* Clap doesn't list its arguments, options before subcommand are list here to find where subcommand begin */
+impl Command {
+    pub const FLAGS: &'static [&'static str] = &["-v", "--verbose", "-q", "--quiet", "-h", "--help", "-V", "--version"];
+    pub const VALUED: &'static [&'static str] = &["-c", "--config", "--log-format"];
+    pub const COUNTED: &'static [&'static str] = &["verbose", "quiet"];
+
+    /* Short flags can be grouped, -vv is -v -v */
+    pub fn is_flag(arg: &str) -> bool {
+        Command::FLAGS.contains(&arg) || (arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v' || c == 'q'))
+    }
+
+    pub fn is_valued(arg: &str) -> bool {
+        Command::VALUED.contains(&arg)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SubCommand {
+    #[structopt(name = "build", about = "Build a barcode graph from mapping of barcode 10x read to assembly")]
+    Build(Box<Build>),
+
+    #[structopt(name = "stats", about = "Summarise a barcode graph")]
+    Stats(Stats),
+
+    #[structopt(name = "ends", about = "Link contig ends which share barcodes")]
+    Ends(Ends),
+
+    #[structopt(name = "scaffold", about = "Order and orient contigs with barcodes shared by their ends")]
+    Scaffold(Scaffold),
+
+    #[structopt(name = "breakpoints", about = "Detect misassembly where barcodes on each side of a position aren't shared")]
+    Breakpoints(Breakpoints),
+
+    #[structopt(name = "merge", about = "Merge barcode graphs of several libraries or lanes")]
+    Merge(Merge),
+
+    #[structopt(name = "compare", about = "Compare two barcode graphs")]
+    Compare(Compare),
+
+    #[structopt(name = "simulate", about = "Simulate mapping of linked reads with known molecules")]
+    Simulate(Simulate),
+
+    #[structopt(name = "evaluate", about = "Score inferred molecules and edges against simulation truth")]
+    Evaluate(Evaluate),
+
+    #[structopt(name = "query", about = "Neighbourhood of a barcode or barcodes of a region in a graph or a molecule table")]
+    Query(Query),
+
+    #[structopt(name = "index", about = "Sort and index a molecule table for region queries and contig selection")]
+    Index(Index),
+}
+
+/* Arguments are name by structopt from their field in kebab case, provenance read them by these names */
+impl SubCommand {
+    /* Options given without subcommand are options of build, as before subcommands exist */
+    pub const DEFAULT: &'static str = "build";
+
+    pub fn args(name: &str) -> Vec<&'static str> {
+        match name {
+            "build" => [Input::ARGS, Build::ARGS].concat(),
+            "stats" => Stats::ARGS.to_vec(),
+            "ends" => [Input::ARGS, Ends::ARGS].concat(),
+            "scaffold" => [Input::ARGS, Scaffold::ARGS].concat(),
+            "breakpoints" => [Input::ARGS, Breakpoints::ARGS].concat(),
+            "merge" => Merge::ARGS.to_vec(),
+            "compare" => Compare::ARGS.to_vec(),
+            "simulate" => Simulate::ARGS.to_vec(),
+            "evaluate" => [Input::ARGS, Evaluate::ARGS].concat(),
+            "query" => Query::ARGS.to_vec(),
+            "index" => Index::ARGS.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Input {
+    #[structopt(short = "e", long = "ema_info", help = "Summary of ema mapping result in tsv: read_id  contig  mapping_position  barcode_id  premolecule_id, can be repeat and prefixed by a library label, label=path, to keep barcodes of each library separated, - read standard input", number_of_values = 1, required_unless = "molecules", conflicts_with = "molecules")]
+    pub ema: Vec<String>,
+
+    #[structopt(long = "molecules", help = "molecule table write by --molecule-table used in place of ema info, indexed table only read selected contigs", requires = "asm")]
+    pub molecules: Option<String>,
+
+    #[structopt(long = "contig", help = "only molecules of this contig are used, can be repeat", number_of_values = 1)]
+    pub contig: Vec<String>,
+
+    #[structopt(short = "a", long = "asm", help = "contig length source: fasta, fasta index, sequence dictionary, sam or gfa, optional if ema info contains @SQ lines")]
+    pub asm: Option<String>,
+
+    #[structopt(short = "l", long = "overlap-length", help = "minimum overlap length", default_value = "9000")]
+    pub threshold: u64,
+
+    #[structopt(short = "p", long = "premolecule-threshold", help = "maximal distance between two read ", default_value = "5000")]
+    pub premolecule: u64,
+
+    #[structopt(long = "min-contig-length", help = "contigs shorter than this are ignored, default is the minimum overlap length")]
+    pub min_contig_length: Option<u64>,
+
+    #[structopt(long = "missing-contig", help = "what to do with contigs present in alignment but absent from assembly", default_value = "keep", possible_values = contig::Missing::NAMES)]
+    pub missing_contig: contig::Missing,
+
+    #[structopt(long = "mask", help = "regions of assembly in bed format where mapping is mask")]
+    pub mask: Option<String>,
+
+    #[structopt(long = "auto-mask", help = "mask windows where number of barcode is upper than this fold of median")]
+    pub auto_mask: Option<f64>,
+
+    #[structopt(long = "mask-window", help = "windows size used by auto mask", default_value = "1000", parse(try_from_str = positive))]
+    pub mask_window: u64,
+
+    #[structopt(long = "mask-mode", help = "exclude mapping in mask before molecule inference or remove masked bases from overlap length", default_value = "exclude", possible_values = mask::Mode::NAMES)]
+    pub mask_mode: mask::Mode,
+
+    #[structopt(long = "mask-output", help = "path where mask used is write in bed")]
+    pub mask_output: Option<String>,
+
+    #[structopt(long = "molecule-table", help = "path where molecules are write in tsv: contig  begin  end  barcode, sorted by contig and begin")]
+    pub molecule_table: Option<String>,
+}
+
+impl Input {
+    pub const ARGS: &'static [&'static str] = &["ema", "molecules", "contig", "asm", "threshold", "premolecule", "min-contig-length", "missing-contig", "mask", "auto-mask", "mask-window", "mask-mode", "mask-output", "molecule-table"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Build {
+    #[structopt(flatten)]
+    pub input: Input,
+
+    #[structopt(short = "o", long = "output", help = "path where barcode graph is write, - write standard output")]
+    pub output: String,
+
+    #[structopt(long = "compress", help = "compression of graph output, by default choose from output extension: .gz, .bz2, .xz or .zst", possible_values = file::COMPRESSION_NAMES, parse(try_from_str = file::compression))]
+    pub compress: Option<file::CompressionFormat>,
+
+    #[structopt(long = "contig-summary", help = "path where barcodes, molecules and edges of each contig are write in tsv")]
+    pub contig_summary: Option<String>,
+
+    #[structopt(short = "w", long = "weight-model", help = "how edge weight is computed", default_value = "max-overlap", possible_values = graph::WeightModel::NAMES)]
+    pub weight_model: graph::WeightModel,
+
+    #[structopt(short = "m", long = "molecule-graph", help = "nodes of graph are molecules instead of barcodes")]
+    pub molecule_graph: bool,
+
+    #[structopt(long = "haplotype", help = "split build one graph per haplotype, annotate label nodes and edges with haplotype of their molecules, haplotype of reads come from HP and PS tags after ema columns, from --haplotag or from --phased-vcf and --read-alleles", possible_values = haplotype::Mode::NAMES)]
+    pub haplotype: Option<haplotype::Mode>,
+
+    #[structopt(long = "haplotag", help = "read haplotypes from whatshap haplotag --output-haplotag-list", requires = "haplotype")]
+    pub haplotag: Option<String>,
+
+    #[structopt(long = "phased-vcf", help = "phased vcf, haplotype of phased heterozygous sites of first sample are used with --read-alleles", requires_all = &["haplotype", "read-alleles"], conflicts_with = "haplotag")]
+    pub phased_vcf: Option<String>,
+
+    #[structopt(long = "read-alleles", help = "alleles of reads at phased sites in tsv: read_name  contig  position  allele, a read take haplotype of majority of its alleles", requires = "phased-vcf")]
+    pub read_alleles: Option<String>,
+
+    #[structopt(long = "fdr", help = "remove edges with a Benjamini-Hochberg adjusted p-value upper than this false discovery rate")]
+    pub fdr: Option<f64>,
+
+    #[structopt(long = "prune", help = prune::Pass::HELP, number_of_values = 1)]
+    pub prune: Vec<prune::Pass>,
+
+    #[structopt(short = "c", long = "community", help = "detect community of barcode graph and neighbourhood of each barcode", possible_values = community::Method::NAMES)]
+    pub community: Option<community::Method>,
+
+    #[structopt(long = "cluster-output", help = "path where barcode cluster is write in tsv", requires = "community")]
+    pub cluster_output: Option<String>,
+
+    #[structopt(long = "neighbourhood-output", help = "path where cluster of each barcode neighbourhood is write in tsv", requires = "community")]
+    pub neighbourhood_output: Option<String>,
+
+    #[structopt(long = "checkpoint-dir", help = "directory where molecules and graphs are save after their construction, a rerun with same inputs and construction parameters resume from last saved stage")]
+    pub checkpoint_dir: Option<String>,
+}
+
+impl Build {
+    pub const ARGS: &'static [&'static str] = &["output", "compress", "contig-summary", "weight-model", "molecule-graph", "haplotype", "haplotag", "phased-vcf", "read-alleles", "fdr", "prune", "community", "cluster-output", "neighbourhood-output", "checkpoint-dir"];
+
+    /* Options apply on graphs after their construction, they don't invalidate a checkpoint */
+    pub const POST_PROCESSING: &'static [&'static str] = &["compress", "fdr", "prune", "community"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Stats {
+    #[structopt(short = "i", long = "input", help = "barcode graph in gexf format, - read standard input")]
+    pub input: String,
+
+    #[structopt(short = "j", long = "json", help = "path where statistics are write in json")]
+    pub json: Option<String>,
+
+    #[structopt(short = "t", long = "tsv", help = "path where statistics are write in tsv, if no output is set tsv is write on standard output")]
+    pub tsv: Option<String>,
+
+    #[structopt(short = "b", long = "bins", help = "number of bins in weight histogram", default_value = "20")]
+    pub bins: usize,
+}
+
+impl Stats {
+    pub const ARGS: &'static [&'static str] = &["input", "json", "tsv", "bins"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Ends {
+    #[structopt(flatten)]
+    pub input: Input,
+
+    #[structopt(short = "o", long = "output", help = "path where contig ends links are write in gfa")]
+    pub output: String,
+
+    #[structopt(short = "W", long = "window", help = "size of contig ends", default_value = "10000")]
+    pub window: u64,
+
+    #[structopt(short = "s", long = "min-shared", help = "minimal number of barcodes shared by two linked ends", default_value = "2")]
+    pub min_shared: usize,
+
+    #[structopt(long = "profiles", help = "path where barcodes of each contig end are write in tsv")]
+    pub profiles: Option<String>,
+}
+
+impl Ends {
+    pub const ARGS: &'static [&'static str] = &["output", "window", "min-shared", "profiles"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Scaffold {
+    #[structopt(flatten)]
+    pub input: Input,
+
+    #[structopt(short = "o", long = "output", help = "path where scaffolds are write in agp")]
+    pub output: String,
+
+    #[structopt(long = "gfa", help = "path where scaffolds are write in gfa")]
+    pub gfa: Option<String>,
+
+    #[structopt(short = "W", long = "window", help = "size of contig ends", default_value = "10000")]
+    pub window: u64,
+
+    #[structopt(short = "s", long = "min-shared", help = "minimal number of barcodes shared by two joined ends", default_value = "2")]
+    pub min_shared: usize,
+
+    #[structopt(short = "M", long = "method", help = "how joins are choose, greedy take best link of each end, spanning-tree drop ends with more than one link in maximum spanning tree", default_value = "greedy", possible_values = scaffold::Method::NAMES)]
+    pub method: scaffold::Method,
+
+    #[structopt(long = "min-gap", help = "minimal length of gap between two joined contigs", default_value = "100")]
+    pub min_gap: u64,
+}
+
+impl Scaffold {
+    pub const ARGS: &'static [&'static str] = &["output", "gfa", "window", "min-shared", "method", "min-gap"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Breakpoints {
+    #[structopt(flatten)]
+    pub input: Input,
+
+    #[structopt(short = "o", long = "output", help = "path where breakpoints are write in bed")]
+    pub output: String,
+
+    #[structopt(short = "W", long = "window", help = "size of windows on each side of a position", default_value = "10000", parse(try_from_str = positive))]
+    pub window: u64,
+
+    #[structopt(long = "step", help = "distance between two tested positions", default_value = "1000", parse(try_from_str = positive))]
+    pub step: u64,
+
+    #[structopt(long = "min-barcodes", help = "minimal number of barcodes on each side of a breakpoint", default_value = "5")]
+    pub min_barcodes: u64,
+
+    #[structopt(long = "max-shared", help = "maximal fraction of barcodes of the smallest side shared with the other side", default_value = "0.1")]
+    pub max_shared: f64,
+
+    #[structopt(long = "split", help = "path where assembly split at breakpoints is write in fasta, assembly must be a fasta", requires = "asm")]
+    pub split: Option<String>,
+}
+
+impl Breakpoints {
+    pub const ARGS: &'static [&'static str] = &["output", "window", "step", "min-barcodes", "max-shared", "split"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Merge {
+    #[structopt(short = "i", long = "input", help = "barcode graph in gexf format, can be prefixed by a library label, label=path, to keep barcodes of each library separated", number_of_values = 1, required = true)]
+    pub inputs: Vec<String>,
+
+    #[structopt(short = "o", long = "output", help = "path where merged barcode graph is write, - write standard output")]
+    pub output: String,
+
+    #[structopt(long = "compress", help = "compression of graph output, by default choose from output extension: .gz, .bz2, .xz or .zst", possible_values = file::COMPRESSION_NAMES, parse(try_from_str = file::compression))]
+    pub compress: Option<file::CompressionFormat>,
+
+    #[structopt(short = "M", long = "method", help = "sum add edge evidence of graphs, number of contigs excepted which is the maximum, max keep the strongest evidence", default_value = "sum", possible_values = merge::Method::NAMES)]
+    pub method: merge::Method,
+}
+
+impl Merge {
+    pub const ARGS: &'static [&'static str] = &["inputs", "output", "compress", "method"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Compare {
+    #[structopt(short = "1", long = "first", help = "first barcode graph in gexf format")]
+    pub first: String,
+
+    #[structopt(short = "2", long = "second", help = "second barcode graph in gexf format")]
+    pub second: String,
+
+    #[structopt(long = "threshold", help = "count edges with a weight crossing this threshold between the two graphs, an absent edge is below threshold")]
+    pub threshold: Option<f64>,
+
+    #[structopt(short = "j", long = "json", help = "path where comparison is write in json")]
+    pub json: Option<String>,
+
+    #[structopt(short = "t", long = "tsv", help = "path where comparison is write in tsv, if no output is set tsv is write on standard output")]
+    pub tsv: Option<String>,
+
+    #[structopt(short = "d", long = "diff", help = "path where union of graphs with a status attribute is write in gexf")]
+    pub diff: Option<String>,
+}
+
+impl Compare {
+    pub const ARGS: &'static [&'static str] = &["first", "second", "threshold", "json", "tsv", "diff"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Simulate {
+    #[structopt(short = "a", long = "asm", help = "contig length source: fasta, fasta index, sequence dictionary, sam or gfa")]
+    pub asm: String,
+
+    #[structopt(short = "o", long = "output", help = "path where simulated mapping is write")]
+    pub output: String,
+
+    #[structopt(short = "t", long = "truth", help = "path where simulated molecules are write in tsv")]
+    pub truth: String,
+
+    #[structopt(short = "f", long = "format", help = "format of simulated mapping, tsv is ema summary format", default_value = "tsv", possible_values = simulate::Format::NAMES)]
+    pub format: simulate::Format,
+
+    #[structopt(short = "b", long = "barcodes", help = "number of barcodes", default_value = "1000")]
+    pub barcodes: usize,
+
+    #[structopt(short = "m", long = "molecules-per-barcode", help = "mean number of molecules per barcode, poisson distributed", default_value = "10")]
+    pub molecules_per_barcode: f64,
+
+    #[structopt(short = "L", long = "molecule-length", help = "mean molecule length, exponentialy distributed", default_value = "50000")]
+    pub molecule_length: f64,
+
+    #[structopt(short = "r", long = "reads-per-molecule", help = "mean number of reads per molecule, poisson distributed", default_value = "50")]
+    pub reads_per_molecule: f64,
+
+    #[structopt(long = "read-length", help = "length of reads", default_value = "150")]
+    pub read_length: u64,
+
+    #[structopt(short = "s", long = "seed", help = "seed of random generator", default_value = "42")]
+    pub seed: u64,
+}
+
+impl Simulate {
+    pub const ARGS: &'static [&'static str] = &["asm", "output", "truth", "format", "barcodes", "molecules-per-barcode", "molecule-length", "reads-per-molecule", "read-length", "seed"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Evaluate {
+    #[structopt(flatten)]
+    pub input: Input,
+
+    #[structopt(long = "truth", help = "simulated molecules in tsv")]
+    pub truth: String,
+
+    #[structopt(short = "g", long = "graph", help = "barcode graph in gexf format to evaluate, by default graph is build from input")]
+    pub graph: Option<String>,
+
+    #[structopt(short = "j", long = "json", help = "path where evaluation is write in json")]
+    pub json: Option<String>,
+
+    #[structopt(short = "t", long = "tsv", help = "path where evaluation is write in tsv, if no output is set tsv is write on standard output")]
+    pub tsv: Option<String>,
+}
+
+impl Evaluate {
+    pub const ARGS: &'static [&'static str] = &["truth", "graph", "json", "tsv"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Query {
+    #[structopt(short = "g", long = "graph", help = "barcode or molecule graph in gexf format", required_unless = "molecule-table", conflicts_with = "molecule-table")]
+    pub graph: Option<String>,
+
+    #[structopt(short = "m", long = "molecule-table", help = "molecule table write by --molecule-table, region query read only indexed bins if table is indexed")]
+    pub molecule_table: Option<String>,
+
+    #[structopt(short = "b", long = "barcode", help = "barcode whose neighbourhood is report", required_unless = "region", conflicts_with = "region")]
+    pub barcode: Option<String>,
+
+    #[structopt(short = "r", long = "region", help = "region, contig:begin-end, whose molecules are report, a graph must be a molecule graph")]
+    pub region: Option<query::Region>,
+
+    #[structopt(short = "d", long = "depth", help = "maximal distance between barcode and its neighbours", default_value = "1")]
+    pub depth: usize,
+
+    #[structopt(short = "l", long = "overlap-length", help = "minimum overlap length of molecules of neighbour barcodes in a molecule table, as in build", default_value = "9000")]
+    pub threshold: u64,
+
+    #[structopt(short = "o", long = "output", help = "path where result is write in tsv, - write standard output", default_value = "-")]
+    pub output: String,
+
+    #[structopt(short = "s", long = "subgraph", help = "path where graph induced by result nodes is write in gexf", requires = "graph")]
+    pub subgraph: Option<String>,
+}
+
+impl Query {
+    pub const ARGS: &'static [&'static str] = &["graph", "molecule-table", "barcode", "region", "depth", "threshold", "output", "subgraph"];
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Index {
+    #[structopt(short = "i", long = "input", help = "molecule table write by --molecule-table")]
+    pub input: String,
+
+    #[structopt(short = "o", long = "output", help = "path where sorted uncompressed table is write, index is write in same path with .idx suffix, default is input")]
+    pub output: Option<String>,
+
+    #[structopt(short = "b", long = "bin", help = "size of index bins", default_value = "16384")]
+    pub bin: u64,
+}
+
+impl Index {
+    pub const ARGS: &'static [&'static str] = &["input", "output", "bin"];
+}
+
+/* Sizes used as divisor can't be 0 */
+pub fn positive(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("value must be upper or equal to 1".to_string()),
+        Ok(value) => Ok(value),
+        Err(e) => Err(format!("{} isn't a positive integer: {}", value, e)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    /* Field names of parsed command, in kebab case, are its argument names, flatten input isn't an argument */
+    fn fields(command: &[&str]) -> Vec<String> {
+        let debug = format!("{:?}", Command::from_iter(command).subcommand).replace("input: Input {", "");
+
+        let mut fields: Vec<String> = debug
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .filter(|word| word.len() > 1 && word.ends_with(':'))
+            .map(|word| word.trim_end_matches(':').replace('_', "-"))
+            .collect();
+        fields.sort();
+
+        fields
+    }
+
+    #[test]
+    fn positive_value() {
+        assert_eq!(positive("1000"), Ok(1000));
+        assert!(positive("0").is_err());
+        assert!(positive("-1").is_err());
+        assert!(positive("2.5").is_err());
+
+        assert!(Command::from_iter_safe(&["m2b", "build", "-e", "a.tsv", "-o", "g.gexf", "--auto-mask", "3", "--mask-window", "0"]).is_err());
+    }
+
+    #[test]
+    fn arguments_are_listed() {
+        let commands: &[&[&str]] = &[
+            &["m2b", "build", "-e", "a.tsv", "-o", "g.gexf"],
+            &["m2b", "stats", "-i", "g.gexf"],
+            &["m2b", "ends", "-e", "a.tsv", "-o", "e.gfa"],
+            &["m2b", "scaffold", "-e", "a.tsv", "-o", "s.agp"],
+            &["m2b", "breakpoints", "-e", "a.tsv", "-o", "b.bed"],
+            &["m2b", "merge", "-i", "g.gexf", "-o", "m.gexf"],
+            &["m2b", "compare", "-1", "a.gexf", "-2", "b.gexf"],
+            &["m2b", "simulate", "-a", "asm.fasta", "-o", "a.tsv", "-t", "t.tsv"],
+            &["m2b", "evaluate", "-e", "a.tsv", "--truth", "t.tsv"],
+            &["m2b", "query", "-g", "g.gexf", "-b", "A"],
+            &["m2b", "index", "-i", "m.tsv"],
+        ];
+
+        for command in commands {
+            let name = command[1];
+            let mut args: Vec<String> = SubCommand::args(name).iter().map(|a| a.to_string()).collect();
+            args.sort();
+            assert_eq!(fields(command), args, "arguments of {}", name);
+        }
+    }
+}
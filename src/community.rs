@@ -0,0 +1,297 @@
+/* project use */
+use crate::graph::{Adjacency, Graph};
+
+/* std use */
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Louvain,
+    LabelPropagation,
+}
+
+impl Method {
+    pub const NAMES: &'static [&'static str] = &["louvain", "label-propagation"];
+}
+
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "louvain" => Ok(Method::Louvain),
+            "label-propagation" => Ok(Method::LabelPropagation),
+            _ => Err(format!("unknow community detection method {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Method::Louvain => write!(f, "louvain"),
+            Method::LabelPropagation => write!(f, "label-propagation"),
+        }
+    }
+}
+
+pub fn clustering(neighbours: &[Vec<(usize, f64)>], method: Method) -> Vec<usize> {
+    match method {
+        Method::Louvain => louvain(neighbours),
+        Method::LabelPropagation => label_propagation(neighbours),
+    }
+}
+
+/* Set cluster of each node and return the modularity of the partition */
+pub fn graph(graph: &mut Graph, method: Method) -> f64 {
+    let (names, neighbours) = graph.adjacency();
+    let names: Vec<String> = names.into_iter().cloned().collect();
+
+    let membership = clustering(&neighbours, method);
+    let quality = modularity(&neighbours, &membership);
+
+    for (name, cluster) in names.iter().zip(membership) {
+        if let Some(node) = graph.nodes.get_mut(name) {
+            node.cluster = Some(cluster);
+        }
+    }
+
+    quality
+}
+
+/* For each barcode, cluster of each neighbour in the subgraph induced by neighbours */
+pub fn neighbourhood(graph: &Graph, method: Method) -> BTreeMap<String, Vec<(String, usize)>> {
+    let (names, neighbours) = graph.adjacency();
+
+    let mut result = BTreeMap::new();
+    for (node, around) in neighbours.iter().enumerate() {
+        let mut local: Vec<usize> = around.iter().map(|(j, _)| *j).collect();
+        local.sort_unstable();
+
+        let global2local: HashMap<usize, usize> = local.iter().enumerate().map(|(i, j)| (*j, i)).collect();
+        let sub_neighbours: Adjacency = local
+            .iter()
+            .map(|j| neighbours[*j].iter().filter_map(|(k, w)| global2local.get(k).map(|l| (*l, *w))).collect())
+            .collect();
+
+        let membership = clustering(&sub_neighbours, method);
+        result.insert(names[node].clone(), local.iter().zip(membership).map(|(j, c)| (names[*j].clone(), c)).collect());
+    }
+
+    result
+}
+
+pub fn write_clusters<W: Write>(writer: &mut W, graph: &Graph) -> std::io::Result<()> {
+    writeln!(writer, "barcode\tcluster")?;
+    for (name, node) in graph.nodes.iter() {
+        if let Some(cluster) = node.cluster {
+            writeln!(writer, "{}\t{}", name, cluster)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_neighbourhood<W: Write>(writer: &mut W, clusters: &BTreeMap<String, Vec<(String, usize)>>) -> std::io::Result<()> {
+    writeln!(writer, "barcode\tneighbour\tcluster")?;
+    for (barcode, neighbours) in clusters.iter() {
+        for (neighbour, cluster) in neighbours {
+            writeln!(writer, "{}\t{}\t{}", barcode, neighbour, cluster)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn modularity(neighbours: &[Vec<(usize, f64)>], membership: &[usize]) -> f64 {
+    let degree: Vec<f64> = neighbours.iter().map(|n| n.iter().map(|(_, w)| w).sum()).collect();
+    let m2: f64 = degree.iter().sum();
+    if m2 == 0.0 {
+        return 0.0;
+    }
+
+    let nb_cluster = membership.iter().max().map(|m| m + 1).unwrap_or(0);
+    let mut internal = vec![0.0; nb_cluster];
+    let mut total = vec![0.0; nb_cluster];
+    for (i, around) in neighbours.iter().enumerate() {
+        total[membership[i]] += degree[i];
+        for (j, w) in around {
+            if membership[*j] == membership[i] {
+                internal[membership[i]] += w;
+            }
+        }
+    }
+
+    internal.iter().zip(total).map(|(i, t)| i / m2 - (t / m2) * (t / m2)).sum()
+}
+
+fn louvain(neighbours: &[Vec<(usize, f64)>]) -> Vec<usize> {
+    let mut membership: Vec<usize> = (0..neighbours.len()).collect();
+
+    let mut level: Adjacency = neighbours.to_vec();
+    let mut self_loop = vec![0.0; neighbours.len()];
+
+    loop {
+        let (community, moved) = local_moving(&level, &self_loop);
+        if !moved {
+            break;
+        }
+
+        let community = renumber(&community);
+        let nb_community = community.iter().max().map(|m| m + 1).unwrap_or(0);
+        if nb_community == level.len() {
+            break;
+        }
+
+        for c in membership.iter_mut() {
+            *c = community[*c];
+        }
+
+        // aggregate each community in one node
+        let mut aggregate: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); nb_community];
+        let mut aggregate_loop = vec![0.0; nb_community];
+        for (i, around) in level.iter().enumerate() {
+            aggregate_loop[community[i]] += self_loop[i];
+            for (j, w) in around {
+                if community[i] == community[*j] {
+                    aggregate_loop[community[i]] += w / 2.0;
+                } else {
+                    *aggregate[community[i]].entry(community[*j]).or_insert(0.0) += w;
+                }
+            }
+        }
+
+        level = aggregate.into_iter().map(|a| a.into_iter().collect()).collect();
+        self_loop = aggregate_loop;
+    }
+
+    renumber(&membership)
+}
+
+fn local_moving(neighbours: &[Vec<(usize, f64)>], self_loop: &[f64]) -> (Vec<usize>, bool) {
+    let degree: Vec<f64> = neighbours.iter().zip(self_loop).map(|(n, l)| n.iter().map(|(_, w)| w).sum::<f64>() + 2.0 * l).collect();
+    let m2: f64 = degree.iter().sum();
+
+    let mut community: Vec<usize> = (0..neighbours.len()).collect();
+    if m2 == 0.0 {
+        return (community, false);
+    }
+
+    let mut total = degree.clone();
+    let mut moved = false;
+
+    loop {
+        let mut improvement = false;
+
+        for i in 0..neighbours.len() {
+            let old = community[i];
+            total[old] -= degree[i];
+
+            let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+            links.insert(old, 0.0);
+            for (j, w) in neighbours[i].iter() {
+                *links.entry(community[*j]).or_insert(0.0) += w;
+            }
+
+            let gain = |c: usize, k_in: f64| k_in - total[c] * degree[i] / m2;
+            let mut best = old;
+            let mut best_gain = gain(old, links[&old]);
+            for (c, k_in) in links.iter() {
+                let g = gain(*c, *k_in);
+                if g > best_gain + 1e-12 {
+                    best = *c;
+                    best_gain = g;
+                }
+            }
+
+            total[best] += degree[i];
+            if best != old {
+                community[i] = best;
+                improvement = true;
+                moved = true;
+            }
+        }
+
+        if !improvement {
+            break;
+        }
+    }
+
+    (community, moved)
+}
+
+fn label_propagation(neighbours: &[Vec<(usize, f64)>]) -> Vec<usize> {
+    let mut labels: Vec<usize> = (0..neighbours.len()).collect();
+
+    for _ in 0..100 {
+        let mut changed = false;
+
+        for i in 0..neighbours.len() {
+            let mut votes: BTreeMap<usize, f64> = BTreeMap::new();
+            for (j, w) in neighbours[i].iter() {
+                *votes.entry(labels[*j]).or_insert(0.0) += w;
+            }
+
+            let max = votes.values().cloned().fold(f64::NEG_INFINITY, f64::max);
+            if votes.get(&labels[i]) == Some(&max) {
+                continue;
+            }
+
+            if let Some((label, _)) = votes.iter().find(|(_, v)| **v == max) {
+                labels[i] = *label;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    renumber(&labels)
+}
+
+/* Cluster are numbered in order of first appearance */
+fn renumber(membership: &[usize]) -> Vec<usize> {
+    let mut old2new = HashMap::new();
+
+    membership
+        .iter()
+        .map(|c| {
+            let next = old2new.len();
+            *old2new.entry(*c).or_insert(next)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    /* two triangles linked by a light edge */
+    fn two_triangles() -> Adjacency {
+        vec![
+            vec![(1, 10.0), (2, 10.0)],
+            vec![(0, 10.0), (2, 10.0)],
+            vec![(0, 10.0), (1, 10.0), (3, 1.0)],
+            vec![(2, 1.0), (4, 10.0), (5, 10.0)],
+            vec![(3, 10.0), (5, 10.0)],
+            vec![(3, 10.0), (4, 10.0)],
+        ]
+    }
+
+    #[test]
+    fn louvain_split_triangles() {
+        let membership = clustering(&two_triangles(), Method::Louvain);
+
+        assert_eq!(membership, vec![0, 0, 0, 1, 1, 1]);
+        assert!(modularity(&two_triangles(), &membership) > 0.4);
+    }
+
+    #[test]
+    fn label_propagation_split_triangles() {
+        assert_eq!(clustering(&two_triangles(), Method::LabelPropagation), vec![0, 0, 0, 1, 1, 1]);
+    }
+}
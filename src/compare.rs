@@ -0,0 +1,207 @@
+/* project use */
+use crate::graph::{self, Graph};
+
+/* crates use */
+use serde::Serialize;
+
+/* std use */
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Comparison {
+    pub nodes_shared: usize,
+    pub nodes_first: usize,
+    pub nodes_second: usize,
+    pub edges_shared: usize,
+    pub edges_first: usize,
+    pub edges_second: usize,
+    pub weight_correlation: Option<f64>,
+    pub components_first: usize,
+    pub components_second: usize,
+    pub components_split: usize,
+    pub components_merged: usize,
+    pub edges_up: Option<usize>,
+    pub edges_down: Option<usize>,
+}
+
+/* Status of an edge weight against threshold, an absent edge is below */
+fn above(graph: &Graph, key: &(String, String), threshold: f64) -> bool {
+    graph.edges.get(key).map(|e| e.weight >= threshold).unwrap_or(false)
+}
+
+pub fn compare(first: &Graph, second: &Graph, threshold: Option<f64>) -> Comparison {
+    let nodes1: BTreeSet<&String> = first.nodes.keys().collect();
+    let nodes2: BTreeSet<&String> = second.nodes.keys().collect();
+    let edges1: BTreeSet<&(String, String)> = first.edges.keys().collect();
+    let edges2: BTreeSet<&(String, String)> = second.edges.keys().collect();
+
+    let weights: Vec<(f64, f64)> = edges1.intersection(&edges2).map(|k| (first.edges[*k].weight, second.edges[*k].weight)).collect();
+
+    let (components_split, components_merged) = component_changes(first, second);
+
+    let flips = threshold.map(|t| {
+        let keys: BTreeSet<&&(String, String)> = edges1.union(&edges2).collect();
+        let up = keys.iter().filter(|k| !above(first, k, t) && above(second, k, t)).count();
+        let down = keys.iter().filter(|k| above(first, k, t) && !above(second, k, t)).count();
+        (up, down)
+    });
+
+    Comparison {
+        nodes_shared: nodes1.intersection(&nodes2).count(),
+        nodes_first: nodes1.difference(&nodes2).count(),
+        nodes_second: nodes2.difference(&nodes1).count(),
+        edges_shared: weights.len(),
+        edges_first: edges1.difference(&edges2).count(),
+        edges_second: edges2.difference(&edges1).count(),
+        weight_correlation: pearson(&weights),
+        components_first: component_number(first),
+        components_second: component_number(second),
+        components_split,
+        components_merged,
+        edges_up: flips.map(|f| f.0),
+        edges_down: flips.map(|f| f.1),
+    }
+}
+
+pub fn pearson(values: &[(f64, f64)]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+
+    let n = values.len() as f64;
+    let mean1 = values.iter().map(|v| v.0).sum::<f64>() / n;
+    let mean2 = values.iter().map(|v| v.1).sum::<f64>() / n;
+
+    let covariance: f64 = values.iter().map(|(a, b)| (a - mean1) * (b - mean2)).sum();
+    let variance1: f64 = values.iter().map(|(a, _)| (a - mean1).powi(2)).sum();
+    let variance2: f64 = values.iter().map(|(_, b)| (b - mean2).powi(2)).sum();
+
+    if variance1 == 0.0 || variance2 == 0.0 {
+        None
+    } else {
+        Some(covariance / (variance1 * variance2).sqrt())
+    }
+}
+
+fn component_number(graph: &Graph) -> usize {
+    let (_, neighbours) = graph.adjacency();
+
+    graph::components(&neighbours).into_iter().collect::<BTreeSet<usize>>().len()
+}
+
+fn node2component(graph: &Graph) -> HashMap<&String, usize> {
+    let (names, neighbours) = graph.adjacency();
+
+    names.into_iter().zip(graph::components(&neighbours)).collect()
+}
+
+/* Components of first graph whose shared nodes are in several components of second graph, and components of second graph gathering shared nodes of several components of first graph */
+fn component_changes(first: &Graph, second: &Graph) -> (usize, usize) {
+    let component1 = node2component(first);
+    let component2 = node2component(second);
+
+    let mut first2second: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+    let mut second2first: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+    for (node, c1) in component1.iter() {
+        if let Some(c2) = component2.get(node) {
+            first2second.entry(*c1).or_default().insert(*c2);
+            second2first.entry(*c2).or_default().insert(*c1);
+        }
+    }
+
+    (first2second.values().filter(|s| s.len() > 1).count(), second2first.values().filter(|s| s.len() > 1).count())
+}
+
+/* Union of both graphs with a status: shared, first or second, edges crossing threshold are up or down, evidence come from second graph when present */
+pub fn diff(first: &Graph, second: &Graph, threshold: Option<f64>) -> Graph {
+    let mut graph = Graph {
+        model: second.model,
+        ..Default::default()
+    };
+
+    for (source, target) in &[(first, "first"), (second, "second")] {
+        for (id, node) in source.nodes.iter() {
+            let status = if first.nodes.contains_key(id) && second.nodes.contains_key(id) { "shared" } else { target };
+            let mut node = node.clone();
+            node.cluster = None;
+            node.status = Some(status.to_string());
+            graph.nodes.insert(id.clone(), node);
+        }
+
+        for (key, edge) in source.edges.iter() {
+            let status = match (first.edges.contains_key(key) && second.edges.contains_key(key), threshold) {
+                (_, Some(t)) if !above(first, key, t) && above(second, key, t) => "up",
+                (_, Some(t)) if above(first, key, t) && !above(second, key, t) => "down",
+                (true, _) => "shared",
+                (false, _) => target,
+            };
+            let mut edge = edge.clone();
+            edge.status = Some(status.to_string());
+            graph.edges.insert(key.clone(), edge);
+        }
+    }
+
+    graph
+}
+
+pub fn write_tsv<W: Write>(writer: &mut W, comparison: &Comparison) -> std::io::Result<()> {
+    let na = |value: Option<String>| value.unwrap_or_else(|| "NA".to_string());
+
+    writeln!(writer, "section\tkey\tvalue")?;
+    writeln!(writer, "nodes\tshared\t{}", comparison.nodes_shared)?;
+    writeln!(writer, "nodes\tfirst\t{}", comparison.nodes_first)?;
+    writeln!(writer, "nodes\tsecond\t{}", comparison.nodes_second)?;
+    writeln!(writer, "edges\tshared\t{}", comparison.edges_shared)?;
+    writeln!(writer, "edges\tfirst\t{}", comparison.edges_first)?;
+    writeln!(writer, "edges\tsecond\t{}", comparison.edges_second)?;
+    writeln!(writer, "edges\tweight_correlation\t{}", na(comparison.weight_correlation.map(|c| c.to_string())))?;
+    writeln!(writer, "edges\tup\t{}", na(comparison.edges_up.map(|c| c.to_string())))?;
+    writeln!(writer, "edges\tdown\t{}", na(comparison.edges_down.map(|c| c.to_string())))?;
+    writeln!(writer, "components\tfirst\t{}", comparison.components_first)?;
+    writeln!(writer, "components\tsecond\t{}", comparison.components_second)?;
+    writeln!(writer, "components\tsplit\t{}", comparison.components_split)?;
+    writeln!(writer, "components\tmerged\t{}", comparison.components_merged)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::{key, Edge, Node};
+
+    fn graph(edges: &[(&str, &str, f64)]) -> Graph {
+        let mut graph = Graph::default();
+        for (node1, node2, weight) in edges {
+            graph.nodes.insert(node1.to_string(), Node::default());
+            graph.nodes.insert(node2.to_string(), Node::default());
+            graph.edges.insert(key(node1, node2), Edge { weight: *weight, ..Default::default() });
+        }
+
+        graph
+    }
+
+    #[test]
+    fn two_graphs() {
+        // B-C edge disappear and split A-B-C-D component
+        let first = graph(&[("A", "B", 10.0), ("B", "C", 12.0), ("C", "D", 20.0)]);
+        let second = graph(&[("A", "B", 11.0), ("C", "D", 5.0), ("D", "E", 15.0)]);
+
+        let comparison = compare(&first, &second, Some(8.0));
+        assert_eq!(comparison.nodes_shared, 4);
+        assert_eq!(comparison.nodes_second, 1);
+        assert_eq!((comparison.edges_shared, comparison.edges_first, comparison.edges_second), (2, 1, 1));
+        assert_eq!(comparison.weight_correlation, Some(-1.0));
+        assert_eq!((comparison.components_split, comparison.components_merged), (1, 0));
+        assert_eq!((comparison.edges_up, comparison.edges_down), (Some(1), Some(2)));
+
+        let diff = diff(&first, &second, Some(8.0));
+        assert_eq!(diff.nodes["E"].status, Some("second".to_string()));
+        assert_eq!(diff.edges[&key("A", "B")].status, Some("shared".to_string()));
+        assert_eq!(diff.edges[&key("C", "D")].status, Some("down".to_string()));
+        assert_eq!(diff.edges[&key("D", "E")].status, Some("up".to_string()));
+    }
+}
@@ -0,0 +1,171 @@
+/* project use */
+use crate::cli;
+
+/* crates use */
+use toml::Value;
+
+/* std use */
+use std::ffi::OsString;
+
+/* Find --config path given before subcommand, return its position, path and position of subcommand or of first option which isn't a global option */
+fn locate(args: &[OsString]) -> (Option<(usize, String)>, Option<usize>) {
+    let mut config = None;
+
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].to_string_lossy();
+        if arg == "-c" || arg == "--config" {
+            config = args.get(index + 1).map(|path| (index, path.to_string_lossy().to_string()));
+            index += 2;
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            config = Some((index, path.to_string()));
+            index += 1;
+        } else if cli::Command::is_valued(&arg) {
+            index += 2;
+        } else if cli::Command::is_flag(&arg) || cli::Command::is_valued(arg.split('=').next().unwrap_or("")) {
+            index += 1;
+        } else {
+            return (config, Some(index));
+        }
+    }
+
+    (config, None)
+}
+
+/* Yaml config is choose from extension, other are toml, both are read in same value tree */
+fn parse(config: &str, path: &str) -> Result<Value, String> {
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(config).map_err(|e| format!("config isn't valid yaml: {}", e))
+    } else {
+        config.parse().map_err(|e| format!("config isn't valid toml: {}", e))
+    }
+}
+
+/* Entries of root which aren't tables are global options, they are insert before subcommand */
+fn global_options(root: &Value) -> Result<Vec<OsString>, String> {
+    let table = match root {
+        Value::Table(table) => table,
+        _ => return Err("config isn't a table".to_string()),
+    };
+
+    let mut options = Vec::new();
+    for (key, value) in table.iter().filter(|(_, value)| !value.is_table()) {
+        match value {
+            Value::Integer(count) if cli::Command::COUNTED.contains(&key.as_str()) => {
+                options.extend((0..*count).map(|_| OsString::from(format!("--{}", key))));
+            }
+            value => push_option(&mut options, key, value, "global")?,
+        }
+    }
+
+    Ok(options)
+}
+
+/* Table of subcommand is convert in long options */
+pub fn options(root: &Value, subcommand: &str) -> Result<Vec<OsString>, String> {
+    let table = match root.get(subcommand) {
+        Some(Value::Table(table)) => table,
+        Some(_) => return Err(format!("config entry {} isn't a table", subcommand)),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut options = Vec::new();
+    for (key, value) in table {
+        push_option(&mut options, key, value, subcommand)?;
+    }
+
+    Ok(options)
+}
+
+/* True boolean is a flag, an array is a repeated option */
+fn push_option(options: &mut Vec<OsString>, key: &str, value: &Value, section: &str) -> Result<(), String> {
+    let values = match value {
+        Value::Array(values) => values.clone(),
+        value => vec![value.clone()],
+    };
+
+    for value in values {
+        let value = match value {
+            Value::Boolean(true) => None,
+            Value::Boolean(false) => continue,
+            Value::String(s) => Some(s),
+            Value::Integer(i) => Some(i.to_string()),
+            Value::Float(f) => Some(f.to_string()),
+            _ => return Err(format!("config value of {}.{} isn't a string, a number or a boolean", section, key)),
+        };
+
+        options.push(OsString::from(format!("--{}", key)));
+        if let Some(value) = value {
+            options.push(OsString::from(value));
+        }
+    }
+
+    Ok(())
+}
+
+/* Without subcommand build is run, options of config are insert just after program name and just after subcommand, options of command line come after and override them */
+pub fn args(mut args: Vec<OsString>) -> Result<Vec<OsString>, String> {
+    let (config, subcommand) = locate(&args);
+    if let Some(index) = subcommand {
+        if args[index].to_string_lossy().starts_with('-') {
+            args.insert(index, OsString::from(cli::SubCommand::DEFAULT));
+        }
+    }
+
+    if let (Some((_, path)), Some(subcommand)) = (config, subcommand) {
+        let config = std::fs::read_to_string(&path).map_err(|e| format!("can't read config {}: {}", path, e))?;
+        let root = parse(&config, &path)?;
+
+        let options = options(&root, &args[subcommand].to_string_lossy())?;
+        args.splice(subcommand + 1..subcommand + 1, options);
+
+        let global = global_options(&root)?;
+        args.splice(1..1, global);
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn strings(options: Vec<OsString>) -> Vec<String> {
+        options.into_iter().map(|o| o.into_string().unwrap()).collect()
+    }
+
+    #[test]
+    fn config_options() {
+        let config = "verbose = 2\nlog-format = \"json\"\n\n[build]\noverlap-length = 8000\nmolecule-graph = true\nfdr = 0.05\nema_info = [\"a.tsv\", \"b.tsv\"]\n\n[stats]\ninput = \"graph.gexf\"\n";
+        let root = parse(config, "run.toml").unwrap();
+
+        assert_eq!(strings(options(&root, "build").unwrap()), vec!["--ema_info", "a.tsv", "--ema_info", "b.tsv", "--fdr", "0.05", "--molecule-graph", "--overlap-length", "8000"]);
+        assert_eq!(strings(global_options(&root).unwrap()), vec!["--log-format", "json", "--verbose", "--verbose"]);
+
+        assert!(options(&root, "merge").unwrap().is_empty());
+        assert!(options(&parse("build = 1", "run.toml").unwrap(), "build").is_err());
+
+        // yaml config give same options
+        let yaml = "verbose: 2\nlog-format: json\nbuild:\n  overlap-length: 8000\n  molecule-graph: true\n  fdr: 0.05\n  ema_info: [a.tsv, b.tsv]\nstats:\n  input: graph.gexf\n";
+        let yaml_root = parse(yaml, "run.yaml").unwrap();
+        assert_eq!(strings(options(&yaml_root, "build").unwrap()), strings(options(&root, "build").unwrap()));
+        assert_eq!(strings(global_options(&yaml_root).unwrap()), strings(global_options(&root).unwrap()));
+        assert!(parse("build: [", "run.yml").is_err());
+
+        let args: Vec<OsString> = ["m2b", "-c", "run.toml", "build", "-e", "a.tsv"].iter().map(OsString::from).collect();
+        assert_eq!(locate(&args), (Some((1, "run.toml".to_string())), Some(3)));
+
+        let args: Vec<OsString> = ["m2b", "-c", "run.toml", "--log-format", "json", "-v", "build", "-o", "x"].iter().map(OsString::from).collect();
+        assert_eq!(locate(&args), (Some((1, "run.toml".to_string())), Some(6)));
+
+        // without subcommand options are options of build
+        let args: Vec<OsString> = ["m2b", "-vv", "--log-format=json", "-a", "asm.fasta", "-e", "a.tsv", "-o", "x"].iter().map(OsString::from).collect();
+        assert_eq!(locate(&args), (None, Some(3)));
+        let expected: Vec<OsString> = ["m2b", "-vv", "--log-format=json", "build", "-a", "asm.fasta", "-e", "a.tsv", "-o", "x"].iter().map(OsString::from).collect();
+        assert_eq!(self::args(args).unwrap(), expected);
+
+        let args: Vec<OsString> = ["m2b", "--help"].iter().map(OsString::from).collect();
+        assert_eq!(self::args(args.clone()).unwrap(), args);
+    }
+}
@@ -0,0 +1,179 @@
+/* project use */
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* crates use */
+use serde::{Deserialize, Serialize};
+
+/* std use */
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Missing {
+    Keep,
+    Skip,
+    Fail,
+}
+
+impl Missing {
+    pub const NAMES: &'static [&'static str] = &["keep", "skip", "fail"];
+}
+
+impl std::str::FromStr for Missing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Missing::Keep),
+            "skip" => Ok(Missing::Skip),
+            "fail" => Ok(Missing::Fail),
+            _ => Err(format!("unknow missing contig action {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Status {
+    Kept,
+    Short,
+    Missing,
+    Skipped,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Status::Kept => write!(f, "kept"),
+            Status::Short => write!(f, "short"),
+            Status::Missing => write!(f, "missing"),
+            Status::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub length: Option<usize>,
+    pub status: Status,
+    pub barcodes: usize,
+    pub molecules: usize,
+    pub edges: u64,
+}
+
+pub type Tig2Summary = BTreeMap<String, Summary>;
+
+/* Remove contigs too short or absent from assembly, and summarise each contig of alignment, contigs without molecules included */
+pub fn filter(tig2barcode2premol2pos: &mut Tig2Barcode2Premol2Pos, aligned: &BTreeSet<String>, tig2len: &HashMap<String, usize>, min_length: u64, missing: Missing) -> Result<Tig2Summary, String> {
+    let mut tig2summary = Tig2Summary::new();
+
+    let no_molecule = HashMap::new();
+    for tig in aligned.iter().chain(tig2barcode2premol2pos.keys()) {
+        let barcode2premol2pos = tig2barcode2premol2pos.get(tig).unwrap_or(&no_molecule);
+        let length = tig2len.get(tig).cloned();
+        let status = match length {
+            Some(len) if (len as u64) < min_length => Status::Short,
+            Some(_) => Status::Kept,
+            None if missing == Missing::Skip => Status::Skipped,
+            None => Status::Missing,
+        };
+
+        tig2summary.insert(
+            tig.clone(),
+            Summary {
+                length,
+                status,
+                barcodes: barcode2premol2pos.values().filter(|p| !p.is_empty()).count(),
+                molecules: barcode2premol2pos.values().map(|p| p.len()).sum(),
+                edges: 0,
+            },
+        );
+    }
+
+    let missing_tigs: Vec<&String> = tig2summary.iter().filter(|(_, s)| s.status == Status::Missing || s.status == Status::Skipped).map(|(t, _)| t).collect();
+    if !missing_tigs.is_empty() {
+        let names = missing_tigs.iter().take(5).map(|t| t.as_str()).collect::<Vec<&str>>().join(", ");
+        let message = format!("{} contigs present in alignment are absent from assembly: {}{}", missing_tigs.len(), names, if missing_tigs.len() > 5 { ", ..." } else { "" });
+
+        if missing == Missing::Fail {
+            return Err(message);
+        }
+        log::warn!("{}", message);
+    }
+
+    tig2barcode2premol2pos.retain(|tig, _| match tig2summary[tig].status {
+        Status::Kept | Status::Missing => true,
+        Status::Short | Status::Skipped => false,
+    });
+
+    Ok(tig2summary)
+}
+
+/* Summary of contigs restricted to some molecules, as molecules of a haplotype, edges are count again */
+pub fn subset(tig2summary: &Tig2Summary, tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos) -> Tig2Summary {
+    tig2summary
+        .iter()
+        .map(|(tig, summary)| {
+            let barcode2premol2pos = tig2barcode2premol2pos.get(tig);
+            let subset = Summary {
+                barcodes: barcode2premol2pos.map(|b| b.values().filter(|p| !p.is_empty()).count()).unwrap_or(0),
+                molecules: barcode2premol2pos.map(|b| b.values().map(|p| p.len()).sum()).unwrap_or(0),
+                edges: 0,
+                ..summary.clone()
+            };
+
+            (tig.clone(), subset)
+        })
+        .collect()
+}
+
+pub fn write_summary<W: Write>(writer: &mut W, tig2summary: &Tig2Summary) -> std::io::Result<()> {
+    writeln!(writer, "contig\tlength\tstatus\tbarcodes\tmolecules\tedges")?;
+    for (tig, summary) in tig2summary.iter() {
+        let length = summary.length.map(|l| l.to_string()).unwrap_or_else(|| "NA".to_string());
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", tig, length, summary.status, summary.barcodes, summary.molecules, summary.edges)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn alignment() -> Tig2Barcode2Premol2Pos {
+        let mut tig2barcode = Tig2Barcode2Premol2Pos::new();
+        for tig in &["tig1", "tig2", "tig3"] {
+            let mut barcode2premol = HashMap::new();
+            barcode2premol.insert("A".to_string(), vec![(0, 20000)]);
+            barcode2premol.insert("B".to_string(), vec![]);
+            tig2barcode.insert(tig.to_string(), barcode2premol);
+        }
+
+        tig2barcode
+    }
+
+    #[test]
+    fn status() {
+        let mut tig2len = HashMap::new();
+        tig2len.insert("tig1".to_string(), 50000);
+        tig2len.insert("tig2".to_string(), 500);
+
+        let mut tig2barcode = alignment();
+        let aligned: BTreeSet<String> = vec!["tig4".to_string()].into_iter().collect();
+        let tig2summary = filter(&mut tig2barcode, &aligned, &tig2len, 1000, Missing::Keep).unwrap();
+        assert_eq!(tig2summary["tig1"].status, Status::Kept);
+        assert_eq!(tig2summary["tig1"].barcodes, 1);
+        assert_eq!(tig2summary["tig2"].status, Status::Short);
+        assert_eq!(tig2summary["tig3"].status, Status::Missing);
+        assert_eq!((tig2summary["tig4"].status, tig2summary["tig4"].molecules), (Status::Missing, 0));
+        assert_eq!(tig2barcode.len(), 2);
+
+        let mut tig2barcode = alignment();
+        let tig2summary = filter(&mut tig2barcode, &BTreeSet::new(), &tig2len, 1000, Missing::Skip).unwrap();
+        assert_eq!(tig2summary["tig3"].status, Status::Skipped);
+        assert_eq!(tig2barcode.len(), 1);
+
+        assert!(filter(&mut alignment(), &BTreeSet::new(), &tig2len, 1000, Missing::Fail).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+/* project use */
+use crate::graph;
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* std use */
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Side {
+    Start,
+    End,
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Side::Start => write!(f, "start"),
+            Side::End => write!(f, "end"),
+        }
+    }
+}
+
+pub type End = (String, Side);
+
+/* For each contig end, barcodes with a molecule in the window and how far this molecule goes inside the contig */
+pub type Profiles = BTreeMap<End, BTreeMap<String, u64>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub end1: End,
+    pub end2: End,
+    pub shared: usize,
+    pub jaccard: f64,
+}
+
+impl Link {
+    /* Orientation of contigs when end1 is follow by end2 */
+    pub fn orientations(&self) -> (char, char) {
+        let first = if self.end1.1 == Side::End { '+' } else { '-' };
+        let second = if self.end2.1 == Side::Start { '+' } else { '-' };
+
+        (first, second)
+    }
+}
+
+pub fn profiles(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2len: &HashMap<String, usize>, window: u64) -> Profiles {
+    let mut profiles = Profiles::new();
+
+    for (tig, barcode2premol2pos) in tig2barcode2premol2pos {
+        let tig_len = graph::tig_length(tig, barcode2premol2pos, tig2len);
+        profiles.entry((tig.clone(), Side::Start)).or_default();
+        profiles.entry((tig.clone(), Side::End)).or_default();
+
+        for (barcode, poss) in barcode2premol2pos {
+            if let Some((_, end)) = poss.iter().filter(|(begin, _)| *begin < window).min() {
+                profiles.get_mut(&(tig.clone(), Side::Start)).unwrap().insert(barcode.clone(), *end);
+            }
+
+            if let Some((begin, _)) = poss.iter().filter(|(_, end)| *end + window > tig_len).max_by_key(|(_, end)| *end) {
+                profiles.get_mut(&(tig.clone(), Side::End)).unwrap().insert(barcode.clone(), tig_len.saturating_sub(*begin));
+            }
+        }
+    }
+
+    profiles
+}
+
+/* Links between ends of different contigs sharing at least min_shared barcodes */
+pub fn links(profiles: &Profiles, min_shared: usize) -> Vec<Link> {
+    let mut barcode2ends: BTreeMap<&String, Vec<&End>> = BTreeMap::new();
+    for (end, barcodes) in profiles.iter() {
+        for barcode in barcodes.keys() {
+            barcode2ends.entry(barcode).or_default().push(end);
+        }
+    }
+
+    let mut shared: BTreeMap<(&End, &End), usize> = BTreeMap::new();
+    for ends in barcode2ends.values() {
+        for (i, end1) in ends.iter().enumerate() {
+            for end2 in ends[i + 1..].iter() {
+                if end1.0 != end2.0 {
+                    *shared.entry((*end1, *end2)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    shared
+        .into_iter()
+        .filter(|(_, nb)| *nb >= min_shared)
+        .map(|((end1, end2), nb)| {
+            let union = profiles[end1].len() + profiles[end2].len() - nb;
+
+            Link {
+                end1: end1.clone(),
+                end2: end2.clone(),
+                shared: nb,
+                jaccard: nb as f64 / union as f64,
+            }
+        })
+        .collect()
+}
+
+pub fn write_profiles<W: Write>(writer: &mut W, profiles: &Profiles) -> std::io::Result<()> {
+    writeln!(writer, "contig\tside\tnb_barcode\tbarcodes")?;
+    for ((tig, side), barcodes) in profiles.iter() {
+        let names: Vec<&str> = barcodes.keys().map(|b| b.as_str()).collect();
+        writeln!(writer, "{}\t{}\t{}\t{}", tig, side, barcodes.len(), names.join(","))?;
+    }
+
+    Ok(())
+}
+
+/* Links are write as gfa L line with unknow overlap, number of shared barcodes in bs tag and jaccard index in jc tag */
+pub fn write_gfa<W: Write>(writer: &mut W, tig2len: &HashMap<String, usize>, profiles: &Profiles, links: &[Link]) -> std::io::Result<()> {
+    writeln!(writer, "H\tVN:Z:1.0")?;
+
+    for ((tig, side), _) in profiles.iter() {
+        if *side == Side::Start {
+            match tig2len.get(tig) {
+                Some(len) => writeln!(writer, "S\t{}\t*\tLN:i:{}", tig, len)?,
+                None => writeln!(writer, "S\t{}\t*", tig)?,
+            }
+        }
+    }
+
+    for link in links {
+        let (orientation1, orientation2) = link.orientations();
+        writeln!(writer, "L\t{}\t{}\t{}\t{}\t*\tbs:i:{}\tjc:f:{}", link.end1.0, orientation1, link.end2.0, orientation2, link.shared, link.jaccard)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn end_links() {
+        let mut tig2barcode = Tig2Barcode2Premol2Pos::new();
+        for (tig, barcode, molecule) in &[("tig1", "A", (85000, 99000)), ("tig1", "B", (90000, 99500)), ("tig1", "C", (1000, 20000)), ("tig2", "A", (10, 9000)), ("tig2", "B", (500, 15000)), ("tig2", "C", (50000, 60000))] {
+            tig2barcode.entry(tig.to_string()).or_default().insert(barcode.to_string(), vec![*molecule]);
+        }
+
+        let mut tig2len = HashMap::new();
+        tig2len.insert("tig1".to_string(), 100000);
+        tig2len.insert("tig2".to_string(), 100000);
+
+        let profiles = profiles(&tig2barcode, &tig2len, 10000);
+        assert_eq!(profiles[&("tig1".to_string(), Side::End)]["A"], 15000);
+        assert_eq!(profiles[&("tig2".to_string(), Side::Start)]["B"], 15000);
+        assert!(profiles[&("tig2".to_string(), Side::End)].is_empty());
+
+        let links = links(&profiles, 2);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].end1, ("tig1".to_string(), Side::End));
+        assert_eq!(links[0].end2, ("tig2".to_string(), Side::Start));
+        assert_eq!(links[0].shared, 2);
+        assert_eq!(links[0].orientations(), ('+', '+'));
+    }
+}
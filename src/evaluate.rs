@@ -0,0 +1,136 @@
+/* project use */
+use crate::graph::{self, Graph};
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* crates use */
+use serde::Serialize;
+
+/* std use */
+use std::collections::BTreeSet;
+use std::io::Write;
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Evaluation {
+    pub truth_molecules: usize,
+    pub inferred_molecules: usize,
+    pub exact_molecules: usize,
+    pub merged_molecules: usize,
+    pub split_molecules: usize,
+    pub missed_molecules: usize,
+    pub truth_edges: usize,
+    pub graph_edges: usize,
+    pub true_edges: usize,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+/* Inferred molecules are compare to truth molecules of same barcode and contig which overlap them, an inferred molecule overlapping several truth molecules is merged and a truth molecule overlapped by several inferred molecules is split */
+pub fn molecules(truth: &Tig2Barcode2Premol2Pos, inferred: &Tig2Barcode2Premol2Pos, evaluation: &mut Evaluation) {
+    let empty = Vec::new();
+
+    for (tig, barcode2truth) in truth {
+        for (barcode, truth_mols) in barcode2truth {
+            let inferred_mols = inferred.get(tig).and_then(|b| b.get(barcode)).unwrap_or(&empty);
+
+            let overlap = |a: &(u64, u64), b: &(u64, u64)| a.0 < b.1 && b.0 < a.1;
+            let by_truth: Vec<usize> = truth_mols.iter().map(|t| inferred_mols.iter().filter(|i| overlap(t, i)).count()).collect();
+            let by_inferred: Vec<usize> = inferred_mols.iter().map(|i| truth_mols.iter().filter(|t| overlap(t, i)).count()).collect();
+
+            evaluation.truth_molecules += truth_mols.len();
+            evaluation.missed_molecules += by_truth.iter().filter(|n| **n == 0).count();
+            evaluation.split_molecules += by_truth.iter().filter(|n| **n > 1).count();
+            evaluation.merged_molecules += by_inferred.iter().filter(|n| **n > 1).count();
+            evaluation.exact_molecules += truth_mols
+                .iter()
+                .zip(by_truth.iter())
+                .filter(|(t, n)| **n == 1 && inferred_mols.iter().zip(by_inferred.iter()).any(|(i, m)| *m == 1 && overlap(t, i)))
+                .count();
+        }
+    }
+
+    evaluation.inferred_molecules = inferred.values().flat_map(|b| b.values()).map(|m| m.len()).sum();
+}
+
+/* Edges of barcode graph against barcodes whose truth molecules overlap more than threshold, nodes of a molecule graph are reduce to their barcode */
+pub fn edges(truth: &Tig2Barcode2Premol2Pos, graph: &Graph, threshold: u64, evaluation: &mut Evaluation) {
+    let mut truth_edges = BTreeSet::new();
+    for barcode2truth in truth.values() {
+        for (barcode1, mols1) in barcode2truth {
+            for (barcode2, mols2) in barcode2truth {
+                if barcode1 >= barcode2 {
+                    continue;
+                }
+
+                let linked = mols1.iter().any(|m1| mols2.iter().any(|m2| m1.1.min(m2.1).saturating_sub(m1.0.max(m2.0)) > threshold));
+                if linked {
+                    truth_edges.insert(graph::key(barcode1, barcode2));
+                }
+            }
+        }
+    }
+
+    let barcode = |id: &String| graph.nodes.get(id).and_then(|n| n.barcode.clone()).unwrap_or_else(|| id.clone());
+    let graph_edges: BTreeSet<(String, String)> = graph
+        .edges
+        .keys()
+        .map(|(node1, node2)| (barcode(node1), barcode(node2)))
+        .filter(|(barcode1, barcode2)| barcode1 != barcode2)
+        .map(|(barcode1, barcode2)| graph::key(&barcode1, &barcode2))
+        .collect();
+
+    evaluation.truth_edges = truth_edges.len();
+    evaluation.graph_edges = graph_edges.len();
+    evaluation.true_edges = graph_edges.intersection(&truth_edges).count();
+    evaluation.precision = if graph_edges.is_empty() { 0.0 } else { evaluation.true_edges as f64 / graph_edges.len() as f64 };
+    evaluation.recall = if truth_edges.is_empty() { 0.0 } else { evaluation.true_edges as f64 / truth_edges.len() as f64 };
+}
+
+pub fn write_tsv<W: Write>(writer: &mut W, evaluation: &Evaluation) -> std::io::Result<()> {
+    writeln!(writer, "section\tkey\tvalue")?;
+    writeln!(writer, "molecules\ttruth\t{}", evaluation.truth_molecules)?;
+    writeln!(writer, "molecules\tinferred\t{}", evaluation.inferred_molecules)?;
+    writeln!(writer, "molecules\texact\t{}", evaluation.exact_molecules)?;
+    writeln!(writer, "molecules\tmerged\t{}", evaluation.merged_molecules)?;
+    writeln!(writer, "molecules\tsplit\t{}", evaluation.split_molecules)?;
+    writeln!(writer, "molecules\tmissed\t{}", evaluation.missed_molecules)?;
+    writeln!(writer, "edges\ttruth\t{}", evaluation.truth_edges)?;
+    writeln!(writer, "edges\tgraph\t{}", evaluation.graph_edges)?;
+    writeln!(writer, "edges\ttrue\t{}", evaluation.true_edges)?;
+    writeln!(writer, "edges\tprecision\t{}", evaluation.precision)?;
+    writeln!(writer, "edges\trecall\t{}", evaluation.recall)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::Edge;
+
+    #[test]
+    fn score() {
+        let mut truth = Tig2Barcode2Premol2Pos::new();
+        let barcode2truth = truth.entry("tig1".to_string()).or_default();
+        barcode2truth.insert("A".to_string(), vec![(0, 30000), (50000, 80000), (100000, 130000)]);
+        barcode2truth.insert("B".to_string(), vec![(10000, 40000)]);
+
+        // A first molecule is exact, the two next are merged, B molecule is split
+        let mut inferred = Tig2Barcode2Premol2Pos::new();
+        let barcode2inferred = inferred.entry("tig1".to_string()).or_default();
+        barcode2inferred.insert("A".to_string(), vec![(100, 29000), (50100, 129000)]);
+        barcode2inferred.insert("B".to_string(), vec![(10100, 20000), (27000, 39000)]);
+
+        let mut evaluation = Evaluation::default();
+        molecules(&truth, &inferred, &mut evaluation);
+        assert_eq!((evaluation.truth_molecules, evaluation.inferred_molecules), (4, 4));
+        assert_eq!((evaluation.exact_molecules, evaluation.merged_molecules, evaluation.split_molecules, evaluation.missed_molecules), (1, 1, 1, 0));
+
+        let mut graph = Graph::default();
+        graph.edges.insert(graph::key("A", "B"), Edge::default());
+        edges(&truth, &graph, 9000, &mut evaluation);
+        assert_eq!((evaluation.truth_edges, evaluation.true_edges), (1, 1));
+        assert_eq!(evaluation.precision, 1.0);
+    }
+}
@@ -20,111 +20,172 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-/* crates use */
-use bzip2;
-use flate2;
-use xz2;
-use enum_primitive::FromPrimitive;
-
 /* standard use */
 use std::fs::File;
 use std::io;
-use std::io::BufReader;
-
-enum_from_primitive! {
-    #[repr(u64)]
-    #[derive(Debug, PartialEq)]
-    pub enum CompressionFormat {
-        Gzip = 0x1F8B,
-        Bzip = 0x425A,
-        Lzma = 0xFD377A585A,
-        No,
+use std::io::{BufRead, BufReader, BufWriter, Read};
+
+/* Value of each format is its magic number */
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionFormat {
+    Gzip = 0x1F8B,
+    Bzip = 0x425A,
+    Lzma = 0xFD377A585A,
+    Zstd = 0x28B52FFD,
+    No,
+}
+
+impl From<CompressionFormat> for niffler::compression::Format {
+    fn from(format: CompressionFormat) -> Self {
+        match format {
+            CompressionFormat::Gzip => niffler::compression::Format::Gzip,
+            CompressionFormat::Bzip => niffler::compression::Format::Bzip,
+            CompressionFormat::Lzma => niffler::compression::Format::Lzma,
+            CompressionFormat::Zstd => niffler::compression::Format::Zstd,
+            CompressionFormat::No => niffler::compression::Format::No,
+        }
     }
 }
 
-pub fn get_readable_file(input_name: &str) -> (Box<io::Read>, CompressionFormat) {
-    let raw_input = get_readable(input_name);
+/* Compression is detected on first bytes, without consume them, decompression is done by niffler */
+pub fn get_readable_file(input_name: &str) -> (Box<dyn io::Read>, CompressionFormat) {
+    let mut raw_input = get_readable(input_name);
 
-    // check compression
-    let compression = get_compression(raw_input);
+    let compression = get_compression(raw_input.fill_buf().unwrap_or_else(|e| panic!("Can't read input file {}: {}", input_name, e)));
 
-    // return readable and compression status
     match compression {
-        CompressionFormat::Gzip => (
-            Box::new(flate2::read::GzDecoder::new(get_readable(input_name))),
-            CompressionFormat::Gzip,
-        ),
-        CompressionFormat::Bzip => (
-            Box::new(bzip2::read::BzDecoder::new(get_readable(input_name))),
-            CompressionFormat::Bzip,
-        ),
-        CompressionFormat::Lzma => (
-            Box::new(xz2::read::XzDecoder::new(get_readable(input_name))),
-            CompressionFormat::Lzma,
-        ),
-        CompressionFormat::No => (Box::new(get_readable(input_name)), CompressionFormat::No),
+        CompressionFormat::No => (Box::new(raw_input), compression),
+        _ => {
+            let (reader, _) = niffler::get_reader(Box::new(raw_input)).unwrap_or_else(|e| panic!("Can't read input file {}: {}", input_name, e));
+            (reader, compression)
+        }
     }
 }
 
-pub fn get_readable(input_name: &str) -> Box<io::Read> {
-    Box::new(
-        BufReader::new(
-            File::open(input_name).expect(&format!("Can't open input file {}", input_name))
-        )
-    )
-}
-
-fn get_compression(mut in_stream: Box<io::Read>) -> CompressionFormat {
-    let mut buf = vec![0u8; 5];
+fn get_compression<R: Read>(in_stream: R) -> CompressionFormat {
+    let mut buf = Vec::with_capacity(5);
+    in_stream.take(5).read_to_end(&mut buf).expect("Error durring reading first bit of file");
 
-    in_stream.read_exact(&mut buf).expect(
-        "Error durring reading first bit of file",
-    );
+    let value = |len: usize| buf.iter().take(len).fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
 
+    if buf.len() >= 5 && value(5) == CompressionFormat::Lzma as u64 {
+        return CompressionFormat::Lzma;
+    }
+    if buf.len() >= 4 && value(4) == CompressionFormat::Zstd as u64 {
+        return CompressionFormat::Zstd;
+    }
 
-    let mut five_bit_val: u64 = 0;
-    for i in 0..5 {
-        five_bit_val |= (buf[i] as u64) << 8 * (4 - i);
+    match value(2) {
+        v if buf.len() >= 2 && v == CompressionFormat::Gzip as u64 => CompressionFormat::Gzip,
+        v if buf.len() >= 2 && v == CompressionFormat::Bzip as u64 => CompressionFormat::Bzip,
+        _ => CompressionFormat::No,
     }
+}
 
-    if CompressionFormat::from_u64(five_bit_val) == Some(CompressionFormat::Lzma) {
-        return CompressionFormat::Lzma;
+/* A dash is standard input, compression is detected on the stream too */
+pub fn get_readable(input_name: &str) -> Box<dyn io::BufRead> {
+    if is_std(input_name) {
+        return Box::new(BufReader::new(io::stdin()));
     }
 
-    let mut two_bit_val: u64 = 0;
-    for i in 0..2 {
-        two_bit_val |= (buf[i] as u64) << 8 * (1 - i);
+    Box::new(BufReader::new(
+        File::open(input_name).unwrap_or_else(|e| panic!("Can't open input file {}: {}", input_name, e)),
+    ))
+}
+
+pub const COMPRESSION_NAMES: &[&str] = &["gzip", "bzip2", "xz", "zstd", "none"];
+
+pub fn compression(name: &str) -> Result<CompressionFormat, String> {
+    match name {
+        "gzip" => Ok(CompressionFormat::Gzip),
+        "bzip2" => Ok(CompressionFormat::Bzip),
+        "xz" => Ok(CompressionFormat::Lzma),
+        "zstd" => Ok(CompressionFormat::Zstd),
+        "none" => Ok(CompressionFormat::No),
+        _ => Err(format!("unknow compression format {}", name)),
     }
+}
 
-    match CompressionFormat::from_u64(two_bit_val) {
-        e @ Some(CompressionFormat::Gzip) |
-        e @ Some(CompressionFormat::Bzip) => e.unwrap(),
+pub fn compression_from_extension(output_name: &str) -> CompressionFormat {
+    match std::path::Path::new(output_name).extension().and_then(|e| e.to_str()) {
+        Some("gz") => CompressionFormat::Gzip,
+        Some("bz2") => CompressionFormat::Bzip,
+        Some("xz") => CompressionFormat::Lzma,
+        Some("zst") => CompressionFormat::Zstd,
         _ => CompressionFormat::No,
     }
 }
 
+/* A dash is standard output, compression is choose from extension */
+pub fn get_writable(output_name: &str) -> Box<dyn io::Write> {
+    get_compressed_writable(output_name, compression_from_extension(output_name))
+}
+
+pub fn get_compressed_writable(output_name: &str, format: CompressionFormat) -> Box<dyn io::Write> {
+    let writer: Box<dyn io::Write> = if is_std(output_name) {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(
+            File::create(output_name).unwrap_or_else(|e| panic!("Can't create output file {}: {}", output_name, e)),
+        ))
+    };
+
+    niffler::get_writer(writer, format.into(), niffler::Level::Six).unwrap_or_else(|e| panic!("Can't compress output file {}: {}", output_name, e))
+}
+
+pub fn is_std(name: &str) -> bool {
+    name == "-"
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
 
-    const GZIP_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0, 0o0];
-    const BZIP_FILE: &'static [u8] = &[0o102, 0o132, 0o0, 0o0, 0o0];
-    const LZMA_FILE: &'static [u8] = &[0o375, 0o067, 0o172, 0o130, 0o132];
+    use std::io::Write;
+
+    const GZIP_FILE: &[u8] = &[0o037, 0o213, 0o0, 0o0, 0o0];
+    const BZIP_FILE: &[u8] = &[0o102, 0o132, 0o0, 0o0, 0o0];
+    const LZMA_FILE: &[u8] = &[0o375, 0o067, 0o172, 0o130, 0o132];
+    const ZSTD_FILE: &[u8] = &[0o050, 0o265, 0o057, 0o375, 0o0];
+
+    #[test]
+    fn compression_from_magic() {
+        assert_eq!(get_compression(GZIP_FILE), CompressionFormat::Gzip);
+        assert_eq!(get_compression(BZIP_FILE), CompressionFormat::Bzip);
+        assert_eq!(get_compression(LZMA_FILE), CompressionFormat::Lzma);
+        assert_eq!(get_compression(ZSTD_FILE), CompressionFormat::Zstd);
+        assert_eq!(get_compression(&b">tig1"[..]), CompressionFormat::No);
+        assert_eq!(get_compression(&[0o037][..]), CompressionFormat::No);
+    }
+
+    #[test]
+    fn compression_from_name() {
+        assert_eq!(compression_from_extension("graph.gexf.gz"), CompressionFormat::Gzip);
+        assert_eq!(compression_from_extension("graph.gexf.zst"), CompressionFormat::Zstd);
+        assert_eq!(compression_from_extension("graph.gexf"), CompressionFormat::No);
+        assert_eq!(compression("xz"), Ok(CompressionFormat::Lzma));
+        assert!(compression("zip").is_err());
+    }
 
     #[test]
     fn compression_from_file() {
-        assert_eq!(
-            get_compression(Box::new(GZIP_FILE)),
-            CompressionFormat::Gzip
-        );
-        assert_eq!(
-            get_compression(Box::new(BZIP_FILE)),
-            CompressionFormat::Bzip
-        );
-        assert_eq!(
-            get_compression(Box::new(LZMA_FILE)),
-            CompressionFormat::Lzma
-        );
+        let dir = std::env::temp_dir();
+        for (ext, format) in &[("gz", CompressionFormat::Gzip), ("bz2", CompressionFormat::Bzip), ("xz", CompressionFormat::Lzma), ("zst", CompressionFormat::Zstd), ("tsv", CompressionFormat::No)] {
+            let path = dir.join(format!("mapping2barcodegraph-file-{}.{}", std::process::id(), ext)).to_string_lossy().to_string();
+
+            let mut writer = get_writable(&path);
+            writer.write_all(b"tig1\t1000\n").unwrap();
+            drop(writer);
+
+            let (mut reader, detected) = get_readable_file(&path);
+            let mut content = String::new();
+            reader.read_to_string(&mut content).unwrap();
+            assert_eq!(detected, *format);
+            assert_eq!(content, "tig1\t1000\n");
+
+            std::fs::remove_file(path).unwrap();
+        }
     }
 }
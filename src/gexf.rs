@@ -0,0 +1,313 @@
+/* project use */
+use crate::graph::{self, Edge, Graph, Node};
+use crate::provenance::Provenance;
+
+/* std use */
+use std::io::{BufRead, Write};
+
+type Attributes = Vec<(&'static str, &'static str, Option<String>)>;
+
+fn node_attributes(node: &Node) -> Attributes {
+    vec![
+        ("molecules", "long", Some(node.molecules.to_string())),
+        ("covered", "long", Some(node.covered.to_string())),
+        ("cluster", "long", node.cluster.map(|c| c.to_string())),
+        ("barcode", "string", node.barcode.clone()),
+        ("contig", "string", node.contig.clone()),
+        ("begin", "long", node.begin.map(|b| b.to_string())),
+        ("end", "long", node.end.map(|e| e.to_string())),
+        ("haplotype", "string", node.haplotype.clone()),
+        ("status", "string", node.status.clone()),
+    ]
+}
+
+fn edge_attributes(edge: &Edge) -> Attributes {
+    vec![
+        ("max_overlap", "long", Some(edge.max_ovl.to_string())),
+        ("sum_overlap", "long", Some(edge.sum_ovl.to_string())),
+        ("pairs", "long", Some(edge.pairs.to_string())),
+        ("contigs", "long", Some(edge.contigs.to_string())),
+        ("expected_pairs", "double", Some(edge.expected_pairs.to_string())),
+        ("expected_overlap", "double", Some(edge.expected_overlap.to_string())),
+        ("pvalue", "double", Some(edge.pvalue.to_string())),
+        ("haplotype", "string", edge.haplotype.clone()),
+        ("status", "string", edge.status.clone()),
+    ]
+}
+
+/* Gexf meta only accept creator, keywords and description, provenance is write in json as keywords */
+pub fn write<W: Write>(writer: &mut W, graph: &Graph, provenance: Option<&Provenance>) -> std::io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">")?;
+    writeln!(writer, "<meta>")?;
+    writeln!(writer, "<creator>{} {}</creator>", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+    if let Some(provenance) = provenance {
+        let json = serde_json::to_string(provenance).map_err(std::io::Error::from)?;
+        writeln!(writer, "<keywords>{}</keywords>", json.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"))?;
+    }
+    writeln!(writer, "<description>weight model: {}</description>", graph.model)?;
+    writeln!(writer, "</meta>")?;
+    writeln!(writer, "<graph mode=\"static\" defaultedgetype=\"undirected\">")?;
+
+    write_attributes(writer, "node", graph.nodes.values().map(node_attributes))?;
+    write_attributes(writer, "edge", graph.edges.values().map(edge_attributes))?;
+
+    writeln!(writer, "<nodes>")?;
+    for (node, attributes) in graph.nodes.iter() {
+        writeln!(writer, "<node id=\"{}\" label=\"{}\">", escape(node), escape(node))?;
+        write_attvalues(writer, node_attributes(attributes))?;
+        writeln!(writer, "</node>")?;
+    }
+    writeln!(writer, "</nodes>")?;
+
+    writeln!(writer, "<edges>")?;
+    for (id, (edge, val)) in graph.edges.iter().enumerate() {
+        writeln!(writer, "<edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\">", id, escape(&edge.0), escape(&edge.1), val.weight)?;
+        write_attvalues(writer, edge_attributes(val))?;
+        writeln!(writer, "</edge>")?;
+    }
+    writeln!(writer, "</edges>")?;
+
+    writeln!(writer, "</graph>")?;
+    writeln!(writer, "</gexf>")?;
+
+    Ok(())
+}
+
+/* Only attributes with a value for at least one element are declared */
+fn write_attributes<W: Write, I: Iterator<Item = Attributes>>(writer: &mut W, class: &str, elements: I) -> std::io::Result<()> {
+    let mut declared: Vec<(&str, &str)> = Vec::new();
+    for attributes in elements {
+        for (name, kind, value) in attributes {
+            if value.is_some() && !declared.contains(&(name, kind)) {
+                declared.push((name, kind));
+            }
+        }
+    }
+
+    writeln!(writer, "<attributes class=\"{}\">", class)?;
+    for (name, kind) in declared {
+        writeln!(writer, "<attribute id=\"{}\" title=\"{}\" type=\"{}\" />", name, name, kind)?;
+    }
+    writeln!(writer, "</attributes>")
+}
+
+fn write_attvalues<W: Write>(writer: &mut W, attributes: Attributes) -> std::io::Result<()> {
+    writeln!(writer, "<attvalues>")?;
+    for (name, _, value) in attributes {
+        if let Some(value) = value {
+            writeln!(writer, "<attvalue for=\"{}\" value=\"{}\" />", name, escape(&value))?;
+        }
+    }
+    writeln!(writer, "</attvalues>")
+}
+
+/* Read a graph write by this tool, one xml element by line */
+pub fn read<R: BufRead>(reader: R) -> std::io::Result<Graph> {
+    let mut graph = Graph::default();
+
+    let mut node: Option<(String, Node)> = None;
+    let mut edge: Option<((String, String), Edge)> = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.starts_with("<description>weight model: ") {
+            let model = line.trim_start_matches("<description>weight model: ").trim_end_matches("</description>");
+            graph.model = model.parse().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        } else if line.starts_with("<node ") {
+            let id = get_attribute(line, "id").ok_or_else(|| invalid("node without id"))?;
+            node = Some((unescape(id), Node::default()));
+        } else if line.starts_with("<edge ") {
+            let source = get_attribute(line, "source").ok_or_else(|| invalid("edge without source"))?;
+            let target = get_attribute(line, "target").ok_or_else(|| invalid("edge without target"))?;
+            let weight = get_attribute(line, "weight").unwrap_or("1").parse().map_err(|_| invalid("edge weight isn't a number"))?;
+
+            edge = Some((graph::key(&unescape(source), &unescape(target)), Edge { weight, ..Default::default() }));
+        } else if line.starts_with("<attvalue ") {
+            let name = get_attribute(line, "for").ok_or_else(|| invalid("attvalue without for"))?;
+            let value = unescape(get_attribute(line, "value").ok_or_else(|| invalid("attvalue without value"))?);
+
+            if let Some((_, n)) = node.as_mut() {
+                set_node_attribute(n, name, &value)?;
+            } else if let Some((_, e)) = edge.as_mut() {
+                set_edge_attribute(e, name, &value)?;
+            }
+        }
+
+        if line.starts_with("</node>") || (line.starts_with("<node ") && line.ends_with("/>")) {
+            if let Some((id, n)) = node.take() {
+                graph.nodes.insert(id, n);
+            }
+        } else if line.starts_with("</edge>") || (line.starts_with("<edge ") && line.ends_with("/>")) {
+            if let Some((key, e)) = edge.take() {
+                graph.edges.insert(key, e);
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+fn set_node_attribute(node: &mut Node, name: &str, value: &str) -> std::io::Result<()> {
+    match name {
+        "molecules" => node.molecules = parse(value)?,
+        "covered" => node.covered = parse(value)?,
+        "cluster" => node.cluster = Some(parse(value)?),
+        "barcode" => node.barcode = Some(value.to_string()),
+        "contig" => node.contig = Some(value.to_string()),
+        "begin" => node.begin = Some(parse(value)?),
+        "end" => node.end = Some(parse(value)?),
+        "haplotype" => node.haplotype = Some(value.to_string()),
+        "status" => node.status = Some(value.to_string()),
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn set_edge_attribute(edge: &mut Edge, name: &str, value: &str) -> std::io::Result<()> {
+    match name {
+        "max_overlap" => edge.max_ovl = parse(value)?,
+        "sum_overlap" => edge.sum_ovl = parse(value)?,
+        "pairs" => edge.pairs = parse(value)?,
+        "contigs" => edge.contigs = parse(value)?,
+        "expected_pairs" => edge.expected_pairs = parse(value)?,
+        "expected_overlap" => edge.expected_overlap = parse(value)?,
+        "pvalue" => edge.pvalue = parse(value)?,
+        "haplotype" => edge.haplotype = Some(value.to_string()),
+        "status" => edge.status = Some(value.to_string()),
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn get_attribute<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=\"", name);
+    let begin = line.find(&pattern)? + pattern.len();
+    let end = line[begin..].find('"')?;
+
+    Some(&line[begin..begin + end])
+}
+
+/* Barcodes, contigs and labels are free text, xml special characters are replaced by entities */
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/* &amp; is replaced last so an escaped entity isn't decoded twice */
+fn unescape(value: &str) -> String {
+    value.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> std::io::Result<T> {
+    value.parse().map_err(|_| invalid(&format!("can't parse attribute value {}", value)))
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph;
+
+    use std::collections::HashMap;
+
+    fn molecules(order: &[&str]) -> crate::parse_info::Tig2Barcode2Premol2Pos {
+        let mut barcode2premol = HashMap::new();
+        for barcode in order {
+            let shift = barcode.as_bytes()[0] as u64 * 100;
+            barcode2premol.insert(barcode.to_string(), vec![(shift, shift + 20000), (50000 + shift, 80000)]);
+        }
+
+        let mut tig2barcode = HashMap::new();
+        tig2barcode.insert("tig1".to_string(), barcode2premol.clone());
+        tig2barcode.insert("tig2".to_string(), barcode2premol);
+
+        tig2barcode
+    }
+
+    #[test]
+    fn same_output_for_same_input() {
+        let tig2len = HashMap::new();
+
+        let mut first = Vec::new();
+        write(&mut first, &graph::build(&molecules(&["A", "B", "C", "D", "E"]), &tig2len, 9000, graph::WeightModel::MaxOverlap, &Default::default(), &mut Default::default()), None).unwrap();
+
+        let mut second = Vec::new();
+        write(&mut second, &graph::build(&molecules(&["A", "B", "C", "D", "E"]), &tig2len, 9000, graph::WeightModel::MaxOverlap, &Default::default(), &mut Default::default()), None).unwrap();
+
+        let mut reverse = Vec::new();
+        write(&mut reverse, &graph::build(&molecules(&["E", "D", "C", "B", "A"]), &tig2len, 9000, graph::WeightModel::MaxOverlap, &Default::default(), &mut Default::default()), None).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first, reverse);
+        assert!(String::from_utf8(first).unwrap().contains("<edge id=\"0\" source=\"A\" target=\"B\" weight=\"19900\">"));
+    }
+
+    /* Each build fill new hash maps, so contigs are iterate in another order */
+    #[test]
+    fn same_output_for_contigs_of_different_length() {
+        let contigs = [("tig1", 100003), ("tig2", 250007), ("tig3", 333331), ("tig4", 1000003), ("tig5", 77777), ("tig6", 612349)];
+
+        let write_build = || {
+            let mut tig2len = HashMap::new();
+            let mut tig2barcode = HashMap::new();
+            for (i, (tig, len)) in contigs.iter().enumerate() {
+                let shift = i as u64 * 1111;
+                let mut barcode2premol = HashMap::new();
+                barcode2premol.insert("A".to_string(), vec![(shift, shift + 20000 + i as u64 * 3)]);
+                barcode2premol.insert("B".to_string(), vec![(shift + 500, shift + 30000 + i as u64 * 7)]);
+
+                tig2len.insert(tig.to_string(), *len);
+                tig2barcode.insert(tig.to_string(), barcode2premol);
+            }
+
+            let mut output = Vec::new();
+            write(&mut output, &graph::build(&tig2barcode, &tig2len, 9000, graph::WeightModel::Pairs, &Default::default(), &mut Default::default()), None).unwrap();
+            output
+        };
+
+        let first = write_build();
+        for _ in 0..20 {
+            assert_eq!(write_build(), first);
+        }
+    }
+
+    #[test]
+    fn read_what_is_write() {
+        let mut graph = graph::build(&molecules(&["A", "B", "C", "D", "E"]), &HashMap::new(), 9000, graph::WeightModel::Jaccard, &Default::default(), &mut Default::default());
+        graph.nodes.get_mut("A").unwrap().cluster = Some(1);
+
+        let mut output = Vec::new();
+        write(&mut output, &graph, None).unwrap();
+
+        assert_eq!(read(&output[..]).unwrap(), graph);
+    }
+
+    #[test]
+    fn special_characters() {
+        let mut graph = graph::build(&molecules(&["A", "B"]), &HashMap::new(), 9000, graph::WeightModel::MaxOverlap, &Default::default(), &mut Default::default());
+        let node = graph.nodes.remove("A").unwrap();
+        let edge = graph.edges.remove(&graph::key("A", "B")).unwrap();
+
+        let id = "lib&1:<A\"'>".to_string();
+        graph.nodes.insert(id.clone(), Node { contig: Some("tig&<\"1".to_string()), status: Some("&amp;".to_string()), ..node });
+        graph.edges.insert(graph::key(&id, "B"), Edge { haplotype: Some("a\"b<c&d".to_string()), ..edge });
+
+        let mut output = Vec::new();
+        write(&mut output, &graph, None).unwrap();
+
+        let text = String::from_utf8(output.clone()).unwrap();
+        assert!(text.contains("<node id=\"lib&amp;1:&lt;A&quot;&apos;&gt;\" label=\"lib&amp;1:&lt;A&quot;&apos;&gt;\">"));
+        assert!(text.contains("value=\"&amp;amp;\""));
+
+        assert_eq!(read(&output[..]).unwrap(), graph);
+    }
+}
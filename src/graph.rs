@@ -0,0 +1,443 @@
+/* project use */
+use crate::contig::Tig2Summary;
+use crate::logging;
+use crate::mask::{self, Mask};
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+use crate::significance;
+
+/* std use */
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightModel {
+    MaxOverlap,
+    SumOverlap,
+    Pairs,
+    Contigs,
+    Jaccard,
+    OverlapCoefficient,
+}
+
+impl WeightModel {
+    pub const NAMES: &'static [&'static str] = &["max-overlap", "sum-overlap", "pairs", "contigs", "jaccard", "overlap-coefficient"];
+}
+
+impl std::str::FromStr for WeightModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "max-overlap" => Ok(WeightModel::MaxOverlap),
+            "sum-overlap" => Ok(WeightModel::SumOverlap),
+            "pairs" => Ok(WeightModel::Pairs),
+            "contigs" => Ok(WeightModel::Contigs),
+            "jaccard" => Ok(WeightModel::Jaccard),
+            "overlap-coefficient" => Ok(WeightModel::OverlapCoefficient),
+            _ => Err(format!("unknow weight model {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for WeightModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            WeightModel::MaxOverlap => "max-overlap",
+            WeightModel::SumOverlap => "sum-overlap",
+            WeightModel::Pairs => "pairs",
+            WeightModel::Contigs => "contigs",
+            WeightModel::Jaccard => "jaccard",
+            WeightModel::OverlapCoefficient => "overlap-coefficient",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Node {
+    pub molecules: u64,
+    pub covered: u64,
+    pub cluster: Option<usize>,
+    pub barcode: Option<String>,
+    pub contig: Option<String>,
+    pub begin: Option<u64>,
+    pub end: Option<u64>,
+    pub haplotype: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Edge {
+    pub weight: f64,
+    pub max_ovl: u64,
+    pub sum_ovl: u64,
+    pub pairs: u64,
+    pub contigs: u64,
+    pub expected_pairs: f64,
+    pub expected_overlap: f64,
+    pub pvalue: f64,
+    pub haplotype: Option<String>,
+    pub status: Option<String>,
+}
+
+/* For each node index, index of neighbours and edge weight */
+pub type Adjacency = Vec<Vec<(usize, f64)>>;
+
+/* Nodes and edges are kept in ordered collections so every writer produces the same output for the same input */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub model: WeightModel,
+    pub nodes: BTreeMap<String, Node>,
+    pub edges: BTreeMap<(String, String), Edge>,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Graph {
+            model: WeightModel::MaxOverlap,
+            nodes: BTreeMap::new(),
+            edges: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn build(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2len: &HashMap<String, usize>, threshold: u64, model: WeightModel, mask: &Mask, tig2summary: &mut Tig2Summary) -> Graph {
+    let mut graph = Graph::default();
+    let mut barcode2node: HashMap<&String, Node> = HashMap::new();
+
+    /* Contigs are visited in name order, expected values are float sums of contigs and must be add in the same order at each run */
+    let mut tigs: Vec<(&String, _)> = tig2barcode2premol2pos.iter().collect();
+    tigs.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut progress = logging::Progress::new("contigs processed", (tigs.len() as u64 / 10).max(1), Some(tigs.len() as u64));
+    for (tig, value) in tigs {
+        progress.inc();
+        let tig_len = tig_length(tig, value, tig2len);
+        let tig_mask = mask.get(tig);
+
+        for (barcode, poss) in value.iter() {
+            let node = barcode2node.entry(barcode).or_default();
+            node.molecules += poss.len() as u64;
+            node.covered += poss.iter().map(|(begin, end)| end - begin).sum::<u64>();
+        }
+
+        let mut tig_edges: HashMap<(&String, &String), Edge> = HashMap::new();
+        for (barcode1, poss1) in value.iter() {
+            for (barcode2, poss2) in value.iter() {
+                if barcode1 >= barcode2 {
+                    continue;
+                }
+
+                for pos1 in poss1 {
+                    for pos2 in poss2 {
+                        if let Some(ovl_len) = get_unmasked_ovl(*pos1, *pos2, tig_mask) {
+                            if ovl_len > threshold {
+                                let edge = tig_edges.entry((barcode1, barcode2)).or_default();
+                                edge.max_ovl = edge.max_ovl.max(ovl_len);
+                                edge.sum_ovl += ovl_len;
+                                edge.pairs += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(summary) = tig2summary.get_mut(tig) {
+            summary.edges += tig_edges.len() as u64;
+        }
+
+        for ((barcode1, barcode2), tig_edge) in tig_edges {
+            let (poss1, poss2) = (&value[barcode1], &value[barcode2]);
+            let covered1 = poss1.iter().map(|(begin, end)| end - begin).sum();
+            let covered2 = poss2.iter().map(|(begin, end)| end - begin).sum();
+
+            let edge = graph.edges.entry((barcode1.clone(), barcode2.clone())).or_default();
+            edge.max_ovl = edge.max_ovl.max(tig_edge.max_ovl);
+            edge.sum_ovl += tig_edge.sum_ovl;
+            edge.pairs += tig_edge.pairs;
+            edge.contigs += 1;
+            edge.expected_pairs += significance::expected_pairs(poss1, poss2, tig_len, threshold);
+            edge.expected_overlap += significance::expected_overlap(covered1, covered2, tig_len);
+        }
+    }
+
+    for edge in graph.edges.values_mut() {
+        edge.pvalue = significance::poisson_sf(edge.pairs, edge.expected_pairs);
+    }
+
+    for (barcode1, barcode2) in graph.edges.keys() {
+        for barcode in &[barcode1, barcode2] {
+            if !graph.nodes.contains_key(*barcode) {
+                graph.nodes.insert(barcode.to_string(), barcode2node[barcode].clone());
+            }
+        }
+    }
+
+    graph.set_weight(model);
+
+    graph
+}
+
+/* Each molecule is a node, molecules of different barcodes are linked if they overlap */
+pub fn build_molecule(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2len: &HashMap<String, usize>, threshold: u64, model: WeightModel, mask: &Mask, tig2summary: &mut Tig2Summary) -> Graph {
+    let mut graph = Graph::default();
+
+    let mut progress = logging::Progress::new("contigs processed", (tig2barcode2premol2pos.len() as u64 / 10).max(1), Some(tig2barcode2premol2pos.len() as u64));
+    for (tig, value) in tig2barcode2premol2pos {
+        progress.inc();
+        let tig_len = tig_length(tig, value, tig2len);
+        let tig_mask = mask.get(tig);
+
+        let mut molecules: Vec<(String, &String, (u64, u64))> = Vec::new();
+        for (barcode, poss) in value.iter() {
+            for pos in poss {
+                molecules.push((format!("{}:{}:{}-{}", barcode, tig, pos.0, pos.1), barcode, *pos));
+            }
+        }
+
+        for (i, (id1, barcode1, pos1)) in molecules.iter().enumerate() {
+            for (id2, barcode2, pos2) in molecules[i + 1..].iter() {
+                if barcode1 == barcode2 {
+                    continue;
+                }
+
+                if let Some(ovl_len) = get_unmasked_ovl(*pos1, *pos2, tig_mask) {
+                    if ovl_len > threshold {
+                        let expected_pairs = significance::expected_pairs(&[*pos1], &[*pos2], tig_len, threshold);
+
+                        if let Some(summary) = tig2summary.get_mut(tig) {
+                            summary.edges += 1;
+                        }
+
+                        graph.edges.insert(
+                            key(id1, id2),
+                            Edge {
+                                max_ovl: ovl_len,
+                                sum_ovl: ovl_len,
+                                pairs: 1,
+                                contigs: 1,
+                                expected_pairs,
+                                expected_overlap: significance::expected_overlap(pos1.1 - pos1.0, pos2.1 - pos2.0, tig_len),
+                                pvalue: significance::poisson_sf(1, expected_pairs),
+                                ..Default::default()
+                            },
+                        );
+
+                        for (id, barcode, pos) in &[(id1, barcode1, pos1), (id2, barcode2, pos2)] {
+                            graph.nodes.entry(id.to_string()).or_insert_with(|| Node {
+                                molecules: 1,
+                                covered: pos.1 - pos.0,
+                                barcode: Some(barcode.to_string()),
+                                contig: Some(tig.clone()),
+                                begin: Some(pos.0),
+                                end: Some(pos.1),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    graph.set_weight(model);
+
+    graph
+}
+
+/* Edges are stored with the smallest node first */
+pub fn key(node1: &str, node2: &str) -> (String, String) {
+    if node1 < node2 {
+        (node1.to_string(), node2.to_string())
+    } else {
+        (node2.to_string(), node1.to_string())
+    }
+}
+
+/* Connected component index of each node */
+pub fn components(neighbours: &[Vec<(usize, f64)>]) -> Vec<usize> {
+    let mut component = vec![usize::MAX; neighbours.len()];
+
+    let mut nb_component = 0;
+    for start in 0..neighbours.len() {
+        if component[start] != usize::MAX {
+            continue;
+        }
+
+        component[start] = nb_component;
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for (next, _) in neighbours[node].iter() {
+                if component[*next] == usize::MAX {
+                    component[*next] = nb_component;
+                    stack.push(*next);
+                }
+            }
+        }
+
+        nb_component += 1;
+    }
+
+    component
+}
+
+/* Contig absent from assembly are assume to end after last molecule */
+pub fn tig_length(tig: &str, barcode2premol2pos: &HashMap<String, Vec<(u64, u64)>>, tig2len: &HashMap<String, usize>) -> u64 {
+    match tig2len.get(tig) {
+        Some(len) => *len as u64,
+        None => barcode2premol2pos.values().flatten().map(|(_, end)| *end).max().unwrap_or(0),
+    }
+}
+
+impl Graph {
+    pub fn set_weight(&mut self, model: WeightModel) {
+        self.model = model;
+
+        let nodes = &self.nodes;
+        for ((barcode1, barcode2), edge) in self.edges.iter_mut() {
+            let covered1 = nodes.get(barcode1).map(|n| n.covered).unwrap_or(0);
+            let covered2 = nodes.get(barcode2).map(|n| n.covered).unwrap_or(0);
+
+            edge.weight = weight(model, edge, covered1, covered2);
+        }
+    }
+
+    /* Nodes in id order and their neighbours */
+    pub fn adjacency(&self) -> (Vec<&String>, Adjacency) {
+        let names: Vec<&String> = self.nodes.keys().collect();
+        let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        let mut neighbours = vec![Vec::new(); names.len()];
+        for ((barcode1, barcode2), edge) in self.edges.iter() {
+            let (i, j) = (index[barcode1], index[barcode2]);
+            neighbours[i].push((j, edge.weight));
+            neighbours[j].push((i, edge.weight));
+        }
+
+        (names, neighbours)
+    }
+
+    pub fn remove_isolated(&mut self) -> usize {
+        let before = self.nodes.len();
+
+        let mut linked = std::collections::BTreeSet::new();
+        for (barcode1, barcode2) in self.edges.keys() {
+            linked.insert(barcode1.clone());
+            linked.insert(barcode2.clone());
+        }
+        self.nodes.retain(|node, _| linked.contains(node));
+
+        before - self.nodes.len()
+    }
+}
+
+pub fn weight(model: WeightModel, edge: &Edge, covered1: u64, covered2: u64) -> f64 {
+    match model {
+        WeightModel::MaxOverlap => edge.max_ovl as f64,
+        WeightModel::SumOverlap => edge.sum_ovl as f64,
+        WeightModel::Pairs => edge.pairs as f64,
+        WeightModel::Contigs => edge.contigs as f64,
+        WeightModel::Jaccard => {
+            let union = (covered1 + covered2).saturating_sub(edge.sum_ovl);
+            if union == 0 {
+                0.0
+            } else {
+                (edge.sum_ovl as f64 / union as f64).min(1.0)
+            }
+        }
+        WeightModel::OverlapCoefficient => {
+            let smallest = covered1.min(covered2);
+            if smallest == 0 {
+                0.0
+            } else {
+                (edge.sum_ovl as f64 / smallest as f64).min(1.0)
+            }
+        }
+    }
+}
+
+/* Overlap length without masked bases */
+pub fn get_unmasked_ovl(pos1: (u64, u64), pos2: (u64, u64), mask: Option<&Vec<(u64, u64)>>) -> Option<u64> {
+    let ovl_len = get_ovl(pos1, pos2)?;
+
+    match mask {
+        Some(intervals) => Some(ovl_len.saturating_sub(mask::masked_length(intervals, pos1.0.max(pos2.0), pos1.1.min(pos2.1)))),
+        None => Some(ovl_len),
+    }
+}
+
+pub fn get_ovl(pos1: (u64, u64), pos2: (u64, u64)) -> Option<u64> {
+    // pos1 contains pos2
+    if pos2.0 > pos1.0 && pos2.1 < pos1.1 {
+        return Some(pos2.1 - pos2.0);
+    }
+
+    // pos2 contains pos1
+    if pos1.0 > pos2.0 && pos1.1 < pos2.1 {
+        return Some(pos1.1 - pos1.0);
+    }
+
+    if pos2.1 > pos1.0 && pos2.1 < pos1.1 {
+        return Some(pos2.1 - pos1.0);
+    }
+
+    if pos1.1 > pos2.0 && pos1.1 < pos2.1 {
+        return Some(pos1.1 - pos2.0);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn weight_models() {
+        let edge = Edge {
+            weight: 0.0,
+            max_ovl: 10000,
+            sum_ovl: 15000,
+            pairs: 2,
+            contigs: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(weight(WeightModel::MaxOverlap, &edge, 20000, 40000), 10000.0);
+        assert_eq!(weight(WeightModel::SumOverlap, &edge, 20000, 40000), 15000.0);
+        assert_eq!(weight(WeightModel::Pairs, &edge, 20000, 40000), 2.0);
+        assert_eq!(weight(WeightModel::Contigs, &edge, 20000, 40000), 1.0);
+        assert_eq!(weight(WeightModel::Jaccard, &edge, 20000, 40000), 15000.0 / 45000.0);
+        assert_eq!(weight(WeightModel::OverlapCoefficient, &edge, 20000, 40000), 0.75);
+        assert_eq!(weight(WeightModel::OverlapCoefficient, &edge, 0, 40000), 0.0);
+    }
+
+    #[test]
+    fn overlap() {
+        assert_eq!(get_ovl((100, 2100), (500, 3000)), Some(1600));
+        assert_eq!(get_ovl((100, 2100), (500, 1000)), Some(500));
+        assert_eq!(get_ovl((100, 2100), (2100, 3000)), None);
+
+        // molecules sharing a begin or an end position aren't overlapping
+        assert_eq!(get_ovl((100, 2100), (500, 2100)), None);
+        assert_eq!(get_ovl((100, 2100), (100, 2100)), None);
+    }
+
+    #[test]
+    fn molecule_graph() {
+        let mut barcode2premol = HashMap::new();
+        barcode2premol.insert("A".to_string(), vec![(0, 20000), (50000, 70000)]);
+        barcode2premol.insert("B".to_string(), vec![(5000, 30000)]);
+
+        let mut tig2barcode = HashMap::new();
+        tig2barcode.insert("tig1".to_string(), barcode2premol);
+
+        let graph = build_molecule(&tig2barcode, &HashMap::new(), 9000, WeightModel::MaxOverlap, &Mask::new(), &mut Tig2Summary::new());
+
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.nodes["A:tig1:0-20000"].barcode, Some("A".to_string()));
+        assert_eq!(graph.edges[&("A:tig1:0-20000".to_string(), "B:tig1:5000-30000".to_string())].weight, 15000.0);
+    }
+}
@@ -0,0 +1,327 @@
+/* project use */
+use crate::file;
+use crate::graph::Graph;
+use crate::parse_info::{self, Tig2Barcode2Premol2Pos};
+
+/* std use */
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, BufReader};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Split,
+    Annotate,
+}
+
+impl Mode {
+    pub const NAMES: &'static [&'static str] = &["split", "annotate"];
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "split" => Ok(Mode::Split),
+            "annotate" => Ok(Mode::Annotate),
+            _ => Err(format!("unknow haplotype mode {}", s)),
+        }
+    }
+}
+
+/* For each contig and barcode, position, phase set and haplotype of phased reads */
+pub type Tig2Barcode2Phased = HashMap<String, HashMap<String, Vec<(u64, u64, u8)>>>;
+
+/* For each contig and barcode, haplotype of each molecule in same order than Tig2Barcode2Premol2Pos */
+pub type Tig2Barcode2Premol2Hap = HashMap<String, HashMap<String, Vec<Option<u8>>>>;
+
+/* Read to phase set and haplotype from whatshap haplotag list: read_name  haplotype  phaseset  chromosome */
+pub fn haplotag(path: &str) -> HashMap<String, (u64, u8)> {
+    let mut read2hap = HashMap::new();
+
+    let (reader, _) = file::get_readable_file(path);
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during haplotag parsing");
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+
+        if let Some(hp) = fields[1].strip_prefix('H').and_then(|h| h.parse::<u8>().ok()) {
+            read2hap.insert(fields[0].to_string(), (fields[2].parse::<u64>().unwrap_or(0), hp));
+        }
+    }
+
+    read2hap
+}
+
+/* For each phased heterozygous site, phase set and allele of haplotype 1 and 2 */
+pub type Site2Alleles = HashMap<(String, u64), (u64, [String; 2])>;
+
+/* Phased heterozygous sites of first sample of a vcf, phase set come from PS format field, 0 if absent */
+pub fn vcf(path: &str) -> Site2Alleles {
+    let mut site2alleles = Site2Alleles::new();
+
+    let (reader, _) = file::get_readable_file(path);
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during vcf parsing");
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 10 {
+            continue;
+        }
+
+        let alleles: Vec<&str> = std::iter::once(fields[3]).chain(fields[4].split(',')).collect();
+        let format: Vec<&str> = fields[8].split(':').collect();
+        let sample: Vec<&str> = fields[9].split(':').collect();
+        let value = |name: &str| format.iter().position(|f| *f == name).and_then(|i| sample.get(i));
+
+        let gt: Vec<Option<&&str>> = match value("GT") {
+            Some(gt) if gt.contains('|') => gt.split('|').map(|a| a.parse::<usize>().ok().and_then(|a| alleles.get(a))).collect(),
+            _ => continue,
+        };
+        let (hp1, hp2) = match gt.as_slice() {
+            [Some(hp1), Some(hp2)] if hp1 != hp2 => (hp1.to_string(), hp2.to_string()),
+            _ => continue,
+        };
+
+        let phase_set = value("PS").and_then(|ps| ps.parse::<u64>().ok()).unwrap_or(0);
+        let pos = fields[1].parse::<u64>().expect("vcf position isn't a number");
+        site2alleles.insert((fields[0].to_string(), pos), (phase_set, [hp1, hp2]));
+    }
+
+    site2alleles
+}
+
+/* Read to phase set and haplotype from alleles of reads at phased sites: read_name  contig  position  allele, a read take haplotype of majority of its alleles in its most represented phase set, tie are unphased */
+pub fn read_alleles(path: &str, site2alleles: &Site2Alleles) -> HashMap<String, (u64, u8)> {
+    let mut read2votes: HashMap<String, BTreeMap<u64, [usize; 2]>> = HashMap::new();
+
+    let (reader, _) = file::get_readable_file(path);
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during read alleles parsing");
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 4 {
+            continue;
+        }
+
+        let pos = fields[2].parse::<u64>().expect("read allele position isn't a number");
+        if let Some((phase_set, alleles)) = site2alleles.get(&(fields[1].to_string(), pos)) {
+            if let Some(hp) = alleles.iter().position(|a| a == fields[3]) {
+                read2votes.entry(fields[0].to_string()).or_default().entry(*phase_set).or_default()[hp] += 1;
+            }
+        }
+    }
+
+    read2votes
+        .into_iter()
+        .filter_map(|(read, votes)| {
+            let (phase_set, counts) = votes.into_iter().max_by_key(|(_, counts)| counts[0] + counts[1])?;
+            match counts[0].cmp(&counts[1]) {
+                std::cmp::Ordering::Greater => Some((read, (phase_set, 1))),
+                std::cmp::Ordering::Less => Some((read, (phase_set, 2))),
+                std::cmp::Ordering::Equal => None,
+            }
+        })
+        .collect()
+}
+
+/* Phased reads of ema tsv, haplotype come from HP and PS tags after the five ema columns or from haplotag list or read alleles */
+pub fn reads(tig2barcode2phased: &mut Tig2Barcode2Phased, tsv_path: &str, label: Option<&str>, read2hap: &HashMap<String, (u64, u8)>) {
+    let (reader, _) = file::get_readable_file(tsv_path);
+    let mut parser = csv::ReaderBuilder::new().delimiter(b'\t').flexible(true).has_headers(false).from_reader(reader);
+    for result in parser.records() {
+        let record = result.expect("Error during ema parsing");
+        if record.len() < 5 || record[0].starts_with('@') {
+            continue;
+        }
+
+        let mut phase_set = 0;
+        let mut hp = None;
+        for field in record.iter().skip(5) {
+            if let Some(value) = field.strip_prefix("HP:i:") {
+                hp = value.parse::<u8>().ok();
+            } else if let Some(value) = field.strip_prefix("PS:i:") {
+                phase_set = value.parse::<u64>().unwrap_or(0);
+            }
+        }
+
+        let (phase_set, hp) = match (hp, read2hap.get(&record[0])) {
+            (Some(hp), _) => (phase_set, hp),
+            (None, Some((phase_set, hp))) => (*phase_set, *hp),
+            (None, None) => continue,
+        };
+
+        let pos = record[2].parse::<u64>().expect("mapping position isn't a number");
+        let barcode = parse_info::namespace(record[3].split('-').next().unwrap(), label);
+        tig2barcode2phased.entry(record[1].to_string()).or_default().entry(barcode).or_default().push((pos, phase_set, hp));
+    }
+}
+
+/* Haplotype of a molecule is the majority haplotype of its reads in its most represented phase set, tie are unphased */
+pub fn molecules(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2barcode2phased: &Tig2Barcode2Phased) -> Tig2Barcode2Premol2Hap {
+    let mut tig2barcode2premol2hap = Tig2Barcode2Premol2Hap::new();
+
+    for (tig, barcode2premol2pos) in tig2barcode2premol2pos {
+        for (barcode, poss) in barcode2premol2pos {
+            let phased = tig2barcode2phased.get(tig).and_then(|b| b.get(barcode));
+
+            let haps = poss
+                .iter()
+                .map(|(begin, end)| {
+                    let mut votes: BTreeMap<u64, BTreeMap<u8, usize>> = BTreeMap::new();
+                    for (_, phase_set, hp) in phased.into_iter().flatten().filter(|(pos, _, _)| begin <= pos && pos <= end) {
+                        *votes.entry(*phase_set).or_default().entry(*hp).or_insert(0) += 1;
+                    }
+
+                    let hp2count = votes.values().max_by_key(|hp2count| hp2count.values().sum::<usize>())?;
+                    let max = hp2count.values().max()?;
+                    let mut best = hp2count.iter().filter(|(_, count)| *count == max);
+
+                    match (best.next(), best.next()) {
+                        (Some((hp, _)), None) => Some(*hp),
+                        _ => None,
+                    }
+                })
+                .collect();
+
+            tig2barcode2premol2hap.entry(tig.clone()).or_default().insert(barcode.clone(), haps);
+        }
+    }
+
+    tig2barcode2premol2hap
+}
+
+/* Molecules assigned to haplotype hp */
+pub fn select(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2barcode2premol2hap: &Tig2Barcode2Premol2Hap, hp: u8) -> Tig2Barcode2Premol2Pos {
+    let mut selected = Tig2Barcode2Premol2Pos::new();
+
+    for (tig, barcode2premol2pos) in tig2barcode2premol2pos {
+        for (barcode, poss) in barcode2premol2pos {
+            let haps = &tig2barcode2premol2hap[tig][barcode];
+            let poss: Vec<(u64, u64)> = poss.iter().zip(haps.iter()).filter(|(_, h)| **h == Some(hp)).map(|(p, _)| *p).collect();
+
+            if !poss.is_empty() {
+                selected.entry(tig.clone()).or_default().insert(barcode.clone(), poss);
+            }
+        }
+    }
+
+    selected
+}
+
+/* Haplotypes present in molecules, sorted */
+pub fn haplotypes(tig2barcode2premol2hap: &Tig2Barcode2Premol2Hap) -> BTreeSet<u8> {
+    tig2barcode2premol2hap.values().flat_map(|b| b.values()).flatten().filter_map(|h| *h).collect()
+}
+
+/* Label nodes with haplotypes of their molecules, and edges with haplotypes where they are found in haplotype graphs */
+pub fn annotate(graph: &mut Graph, tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, tig2barcode2premol2hap: &Tig2Barcode2Premol2Hap, hap_graphs: &[(u8, Graph)]) {
+    let mut barcode2haps: HashMap<&String, BTreeSet<u8>> = HashMap::new();
+    for barcode2premol2hap in tig2barcode2premol2hap.values() {
+        for (barcode, haps) in barcode2premol2hap {
+            barcode2haps.entry(barcode).or_default().extend(haps.iter().filter_map(|h| *h));
+        }
+    }
+
+    for (id, node) in graph.nodes.iter_mut() {
+        let haps = match (&node.barcode, &node.contig, node.begin, node.end) {
+            (Some(barcode), Some(tig), Some(begin), Some(end)) => tig2barcode2premol2pos[tig][barcode]
+                .iter()
+                .zip(tig2barcode2premol2hap[tig][barcode].iter())
+                .filter(|(pos, _)| **pos == (begin, end))
+                .filter_map(|(_, h)| *h)
+                .collect(),
+            _ => barcode2haps.get(id).cloned().unwrap_or_default(),
+        };
+
+        node.haplotype = Some(label(&haps));
+    }
+
+    for (key, edge) in graph.edges.iter_mut() {
+        let haps = hap_graphs.iter().filter(|(_, g)| g.edges.contains_key(key)).map(|(hp, _)| *hp).collect();
+
+        edge.haplotype = Some(label(&haps));
+    }
+}
+
+/* Path of haplotype output, haplotype is insert before extension */
+pub fn path(path: &str, hp: u8) -> String {
+    let path = std::path::Path::new(path);
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!("{}.hp{}.{}", stem.to_string_lossy(), hp, ext.to_string_lossy()),
+        _ => format!("{}.hp{}", path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default(), hp),
+    };
+
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+pub fn label(haps: &BTreeSet<u8>) -> String {
+    if haps.is_empty() {
+        "unphased".to_string()
+    } else {
+        haps.iter().map(|h| h.to_string()).collect::<Vec<String>>().join(",")
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn molecule_haplotype() {
+        let mut tig2barcode = Tig2Barcode2Premol2Pos::new();
+        tig2barcode.entry("tig1".to_string()).or_default().insert("A".to_string(), vec![(0, 10000), (20000, 30000), (40000, 50000)]);
+
+        let mut tig2phased = Tig2Barcode2Phased::new();
+        tig2phased.entry("tig1".to_string()).or_default().insert(
+            "A".to_string(),
+            vec![(100, 1, 1), (200, 1, 1), (300, 1, 2), (20100, 1, 2), (20200, 2, 1), (20300, 2, 1), (40100, 1, 1), (40200, 1, 2)],
+        );
+
+        let haps = molecules(&tig2barcode, &tig2phased);
+        assert_eq!(haps["tig1"]["A"], vec![Some(1), Some(1), None]);
+        assert_eq!(haplotypes(&haps).into_iter().collect::<Vec<u8>>(), vec![1]);
+
+        let selected = select(&tig2barcode, &haps, 1);
+        assert_eq!(selected["tig1"]["A"], vec![(0, 10000), (20000, 30000)]);
+    }
+
+    #[test]
+    fn vcf_read_alleles() {
+        let dir = std::env::temp_dir();
+        let vcf_path = dir.join(format!("mapping2barcodegraph-phased-{}.vcf", std::process::id())).to_string_lossy().to_string();
+        let alleles_path = dir.join(format!("mapping2barcodegraph-alleles-{}.tsv", std::process::id())).to_string_lossy().to_string();
+
+        std::fs::write(
+            &vcf_path,
+            "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ntig1\t100\t.\tA\tC\t.\tPASS\t.\tGT:PS\t0|1:90\ntig1\t200\t.\tG\tT,C\t.\tPASS\t.\tGT:PS\t2|1:90\ntig1\t300\t.\tA\tC\t.\tPASS\t.\tGT\t0/1\ntig1\t400\t.\tA\tC\t.\tPASS\t.\tGT\t1|1\n",
+        )
+        .unwrap();
+        let site2alleles = vcf(&vcf_path);
+        assert_eq!(site2alleles.len(), 2);
+        assert_eq!(site2alleles[&("tig1".to_string(), 200)], (90, ["C".to_string(), "T".to_string()]));
+
+        std::fs::write(&alleles_path, "r1\ttig1\t100\tA\nr1\ttig1\t200\tC\nr2\ttig1\t100\tC\nr3\ttig1\t100\tA\nr3\ttig1\t200\tT\nr4\ttig1\t300\tA\n").unwrap();
+        let read2hap = read_alleles(&alleles_path, &site2alleles);
+        assert_eq!(read2hap.get("r1"), Some(&(90, 1)));
+        assert_eq!(read2hap.get("r2"), Some(&(90, 2)));
+        assert_eq!(read2hap.get("r3"), None);
+        assert_eq!(read2hap.get("r4"), None);
+
+        std::fs::remove_file(vcf_path).unwrap();
+        std::fs::remove_file(alleles_path).unwrap();
+    }
+}
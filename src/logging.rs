@@ -0,0 +1,166 @@
+/* crates use */
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+
+/* std use */
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub const NAMES: &'static [&'static str] = &["text", "json"];
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknow log format {}", s)),
+        }
+    }
+}
+
+/* Stages ended, in order, for timing summary */
+static STAGES: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    time: f64,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+}
+
+struct Logger {
+    format: Format,
+    level: LevelFilter,
+    start: Instant,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let time = self.start.elapsed().as_secs_f64();
+        match self.format {
+            Format::Text => eprintln!("[{:9.3}s] {}: {}", time, record.level(), record.args()),
+            Format::Json => {
+                let line = JsonRecord {
+                    time,
+                    level: record.level().as_str(),
+                    target: record.target(),
+                    message: record.args().to_string(),
+                };
+                eprintln!("{}", serde_json::to_string(&line).unwrap_or_default());
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/* Info by default, each -v add a level, each -q remove one, errors are always report */
+pub fn init(format: Format, verbose: u8, quiet: u8) {
+    let levels = [LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug, LevelFilter::Trace];
+    let level = levels[(2 + verbose as usize).saturating_sub(quiet as usize).min(levels.len() - 1)];
+
+    log::set_boxed_logger(Box::new(Logger { format, level, start: Instant::now() })).expect("logger is already set");
+    log::set_max_level(level);
+}
+
+pub fn duration(duration: Duration) -> String {
+    format!("{}.{:03}s", duration.as_secs(), duration.subsec_millis())
+}
+
+pub struct Stage {
+    name: String,
+    begin: Instant,
+}
+
+impl Stage {
+    pub fn new(name: &str) -> Self {
+        log::info!(target: "stage", "{} begin", name);
+
+        Stage { name: name.to_string(), begin: Instant::now() }
+    }
+
+    pub fn end(self) {
+        let elapsed = self.begin.elapsed();
+        log::info!(target: "stage", "{} end {}", self.name, duration(elapsed));
+
+        STAGES.lock().expect("stage timings are poisoned").push((self.name, elapsed));
+    }
+}
+
+/* Report count of processed items every step */
+pub struct Progress {
+    what: &'static str,
+    step: u64,
+    count: u64,
+    total: Option<u64>,
+}
+
+impl Progress {
+    pub fn new(what: &'static str, step: u64, total: Option<u64>) -> Self {
+        Progress { what, step: step.max(1), count: 0, total }
+    }
+
+    pub fn inc(&mut self) {
+        self.count += 1;
+        if self.count.is_multiple_of(self.step) {
+            match self.total {
+                Some(total) => log::info!(target: "progress", "{} {}/{}", self.what, self.count, total),
+                None => log::info!(target: "progress", "{} {}", self.what, self.count),
+            }
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/* Peak resident memory in kB, only available on linux */
+pub fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+
+    line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()
+}
+
+pub fn summary() {
+    for (name, elapsed) in STAGES.lock().expect("stage timings are poisoned").iter() {
+        log::info!(target: "summary", "stage {} {}", name, duration(*elapsed));
+    }
+
+    if let Some(memory) = peak_memory() {
+        log::info!(target: "summary", "peak memory {} kB", memory);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn millisecond_padding() {
+        assert_eq!(duration(Duration::from_millis(2005)), "2.005s");
+        assert_eq!(duration(Duration::from_millis(150)), "0.150s");
+        assert!(peak_memory().map(|m| m > 0).unwrap_or(true));
+    }
+}
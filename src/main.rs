@@ -1,160 +1,736 @@
 
 /* project mod */
+mod cli;
+mod breakpoint;
+mod checkpoint;
+mod community;
+mod compare;
+mod config;
+mod contig;
+mod ends;
+mod evaluate;
+mod file;
+mod gexf;
+mod graph;
+mod haplotype;
+mod logging;
+mod mask;
+mod merge;
 mod parse_info;
+mod prune;
+mod query;
+mod provenance;
+mod scaffold;
+mod significance;
+mod simulate;
+mod statistics;
+mod table;
 //mod premolecule;
 
 /* crates use */
+use serde::{Deserialize, Serialize};
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
 /* std use */
-use std::io::Write;
-use std::time;
-use std::collections::HashSet;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-#[derive(Debug, StructOpt)]
-#[structopt(name = "mapping2barcode", about = "Use mapping of barcode 10x read to assembly to build a barcode graph", author = "Pierre Marijon <pmarijon@mpi-inf.mpg.de>")]
-struct Command {
-    #[structopt(short = "e", long = "ema_info", help = "Summary of ema mapping result in tsv: read_id  contig  mapping_position  barcode_id  premolecule_id")]
-    ema: String,
+fn main() {
+    let args = match config::args(std::env::args_os().collect()) {
+	Ok(args) => args,
+	Err(message) => {
+	    eprintln!("ERROR: {}", message);
+	    std::process::exit(1);
+	}
+    };
 
-    #[structopt(short = "a", long = "asm", help = "assembly in fasta format")]
-    asm: String,
+    /* Options of config file are before options of command line, last one win */
+    let matches = cli::Command::clap().global_setting(AppSettings::AllArgsOverrideSelf).get_matches_from(args);
+    let command = cli::Command::from_clap(&matches);
+    logging::init(command.log_format, command.verbose, command.quiet);
 
-    #[structopt(short = "o", long = "output", help = "path where barcode graph is write")]
-    output: String,
+    if let Some(path) = &command.config {
+	log::info!("options read from config {}", path);
+    }
 
-    #[structopt(short = "l", long = "overlap-length", help = "minimum overlap length", default_value = "9000")]
-    threshold: u64,
+    let stage = logging::Stage::new("checksum inputs");
+    let provenance = provenance::Provenance::new(&matches);
+    stage.end();
 
-    #[structopt(short = "p", long = "premolecule-threshold", help = "maximal distance between two read ", default_value = "5000")]
-    premolecule: u64,
+    match command.subcommand {
+	cli::SubCommand::Build(params) => build(*params, &provenance),
+	cli::SubCommand::Stats(params) => stats(params, &provenance),
+	cli::SubCommand::Ends(params) => contig_ends(params),
+	cli::SubCommand::Scaffold(params) => scaffolding(params),
+	cli::SubCommand::Breakpoints(params) => breakpoints(params),
+	cli::SubCommand::Merge(params) => merge_graphs(params, &provenance),
+	cli::SubCommand::Compare(params) => compare_graphs(params, &provenance),
+	cli::SubCommand::Simulate(params) => simulation(params),
+	cli::SubCommand::Evaluate(params) => evaluation(params, &provenance),
+	cli::SubCommand::Query(params) => querying(params, &provenance),
+	cli::SubCommand::Index(params) => indexing(params),
+    }
+
+    logging::summary();
 }
 
+#[derive(Serialize, Deserialize)]
+struct Molecules {
+    tig2barcode2premol2pos: parse_info::Tig2Barcode2Premol2Pos,
+    tig2len: HashMap<String, usize>,
+    tig2summary: contig::Tig2Summary,
+    mask: mask::Mask,
+    masked: mask::Mask,
+}
 
-fn main() {
-    let params = Command::from_args();
+/* Outputs of molecule inference, write again when molecules are load from checkpoint */
+fn molecule_outputs(params: &cli::Input, molecules: &Molecules) {
+    if let Some(path) = &params.mask_output {
+	let mut writer = file::get_writable(path);
+	mask::write_bed(&mut writer, &molecules.masked).expect("error durring mask write");
+    }
+
+    if let Some(path) = &params.molecule_table {
+	table::remove_index(path);
+	let mut writer = file::get_writable(path);
+	table::write(&mut writer, &molecules.tig2barcode2premol2pos).expect("error durring molecule table write");
+    }
+}
+
+fn molecules(params: &cli::Input) -> Molecules {
+
+    if params.ema.iter().filter(|input| file::is_std(parse_info::library(input).1)).count() > 1 {
+	log::error!("standard input can be used by only one ema info");
+	std::process::exit(1);
+    }
     
+    if params.molecules.is_some() && (params.auto_mask.is_some() || (params.mask.is_some() && params.mask_mode == mask::Mode::Exclude)) {
+	log::error!("molecule table contains no mapping, auto mask and exclude mask mode can't be used");
+	std::process::exit(1);
+    }
+
     /* Read ema information */
-    eprintln!("read ema info\n\tbegin");
-    let mut begin = time::Instant::now();
+    let mut tig2barcode2poss = parse_info::Tig2Barcode2Poss::new();
+    let mut header_tig2len = HashMap::new();
+    if params.molecules.is_none() {
+	let stage = logging::Stage::new("read ema info");
 
-    let tig2barcode2premol2pos = parse_info::ema(params.ema, params.premolecule, params.threshold);
+	for input in params.ema.iter() {
+	    let (label, path) = parse_info::library(input);
+	    let (library_poss, library_len) = parse_info::ema(path.to_string(), label);
 
-    let mut duration = time::Instant::now() - begin;
-    eprintln!("\tend {}s{}", duration.as_secs(), duration.subsec_millis());
+	    for (tig, barcode2poss) in library_poss {
+		let tig_poss = tig2barcode2poss.entry(tig).or_default();
+		for (barcode, poss) in barcode2poss {
+		    tig_poss.entry(barcode).or_default().extend(poss);
+		}
+	    }
+	    header_tig2len.extend(library_len);
+	}
+
+	if !params.contig.is_empty() {
+	    tig2barcode2poss.retain(|tig, _| params.contig.contains(tig));
+	}
+
+	stage.end();
+    }
+
+    /* Contigs of alignment, summarise even if all their mappings are masked or their barcodes have no molecule */
+    let aligned: BTreeSet<String> = tig2barcode2poss.keys().cloned().collect();
 
     
     /* Read contig graph information */
-    eprintln!("read assembly\n\tbegin");
-    begin = time::Instant::now();
+    let stage = logging::Stage::new("read assembly");
 
-    let tig2len = parse_info::assembly(params.asm);
+    let tig2len = match &params.asm {
+	Some(path) => parse_info::assembly(path.clone()),
+	None if !header_tig2len.is_empty() => header_tig2len,
+	None => {
+	    log::error!("ema info contains no @SQ lines, contig length source (-a) is required");
+	    std::process::exit(1);
+	}
+    };
 
-    duration = time::Instant::now() - begin;
-    eprintln!("\tend {}s{}", duration.as_secs(), duration.subsec_millis());
+    stage.end();
 
-    
-    eprintln!("found edge of barcode graph\n\tbegin");
-    begin = time::Instant::now();
-    
-    let mut nodes = HashSet::new();
-    let mut edges = HashMap::new();
-    for (tig, value) in tig2barcode2premol2pos {
-	if let Some(len) = tig2len.get(&tig) {
-	    if len < &(params.threshold as usize) {
-		continue;
-	    }
+    let mut mask = mask::Mask::new();
+    let mut masked = mask::Mask::new();
+    if params.mask.is_some() || params.auto_mask.is_some() {
+	let stage = logging::Stage::new("mask assembly");
+
+	if let Some(path) = &params.mask {
+	    mask = mask::read_bed(path).unwrap_or_else(|message| {
+		log::error!("{}", message);
+		std::process::exit(1);
+	    });
 	}
 
-	for (barcode1, poss1) in value.iter() {
-	    for (barcode2, poss2) in value.iter() {
-	    
-		if barcode1 == barcode2 {
-		    continue;
-		}
+	if let Some(fold) = params.auto_mask {
+	    mask::extend(&mut mask, mask::auto(&tig2barcode2poss, params.mask_window, fold));
+	}
+
+	masked = mask.clone();
+	if params.mask_mode == mask::Mode::Exclude {
+	    let removed = mask::exclude(&mut tig2barcode2poss, &mask);
+	    log::info!("{} mapping in masked region removed", removed);
+	    mask.clear();
+	}
+
+	stage.end();
+    }
+
+    let mut tig2barcode2premol2pos = match &params.molecules {
+	Some(path) => {
+	    let stage = logging::Stage::new("read molecule table");
+	    let molecules = table::read_contigs(path, &params.contig);
+	    stage.end();
+
+	    molecules
+	}
+	None => parse_info::premolecule(tig2barcode2poss, params.premolecule, params.threshold),
+    };
+
+    let min_contig_length = params.min_contig_length.unwrap_or(params.threshold);
+    let tig2summary = match contig::filter(&mut tig2barcode2premol2pos, &aligned, &tig2len, min_contig_length, params.missing_contig) {
+	Ok(tig2summary) => tig2summary,
+	Err(message) => {
+	    log::error!("{}", message);
+	    std::process::exit(1);
+	}
+    };
+
+    let molecules = Molecules {
+	tig2barcode2premol2pos,
+	tig2len,
+	tig2summary,
+	mask,
+	masked,
+    };
+    molecule_outputs(params, &molecules);
+
+    molecules
+}
+
+fn build(params: cli::Build, provenance: &provenance::Provenance) {
+    if params.haplotype.is_some() && params.input.molecules.is_some() {
+	log::error!("haplotype mode read haplotype of reads in ema info, molecule table can't be used");
+	std::process::exit(1);
+    }
+
+    if params.haplotype.is_some() && params.input.ema.iter().any(|input| file::is_std(parse_info::library(input).1)) {
+	log::error!("haplotype mode read ema info twice, standard input can't be used");
+	std::process::exit(1);
+    }
+
+    if params.haplotype == Some(haplotype::Mode::Split) && (file::is_std(&params.output) || params.contig_summary.iter().any(|path| file::is_std(path))) {
+	log::error!("haplotype split mode write one graph and one contig summary by haplotype, standard output can't be used");
+	std::process::exit(1);
+    }
+
+    let mut checkpoint = match &params.checkpoint_dir {
+	Some(_) if params.input.ema.iter().any(|input| file::is_std(parse_info::library(input).1)) => {
+	    log::warn!("standard input can't be identified, checkpoint is disable");
+	    None
+	}
+	Some(dir) => Some(checkpoint::Checkpoint::open(dir, provenance)),
+	None => None,
+    };
 
-		for pos1 in poss1 {
-		    for pos2 in poss2 {
-			if let Some(ovl_len) = get_ovl(*pos1, *pos2) {
-			    if ovl_len > params.threshold {
-				nodes.insert(barcode1.clone());
-				nodes.insert(barcode2.clone());
-				if barcode1 < barcode2 {
-				    let mut val = edges.entry((barcode1.clone(), barcode2.clone())).or_insert(ovl_len);
-				    if *val < ovl_len {
-					*val = ovl_len;
-				    }
-				} else {
-				    let mut val = edges.entry((barcode2.clone(), barcode1.clone())).or_insert(ovl_len);
-
-				    if *val < ovl_len {
-					*val = ovl_len;
-				    }
-				}
-			    }
-			}
+    let graphs = match checkpoint.as_ref().and_then(|c| c.graphs()) {
+	Some(saved) => {
+	    if params.input.mask_output.is_some() || params.input.molecule_table.is_some() {
+		match checkpoint.as_ref().and_then(|c| c.molecules()) {
+		    Some(molecules) => molecule_outputs(&params.input, &molecules),
+		    None => {
+			log::error!("molecules aren't in checkpoint, mask and molecule table can't be write, remove checkpoint directory to rerun");
+			std::process::exit(1);
 		    }
 		}
 	    }
+
+	    saved
+	}
+	None => {
+	    let graphs = build_graphs(&params, &mut checkpoint);
+	    if let Some(checkpoint) = checkpoint.as_mut() {
+		checkpoint.save_graphs(&graphs);
+	    }
+
+	    graphs
 	}
+    };
+
+    for (hp, graph, tig2summary) in graphs {
+	if let Some(hp) = hp {
+	    log::info!("haplotype {}", hp);
+	}
+
+	if let Some(path) = &params.contig_summary {
+	    let mut writer = file::get_writable(&hp.map(|hp| haplotype::path(path, hp)).unwrap_or_else(|| path.clone()));
+	    contig::write_summary(&mut writer, &tig2summary).expect("error durring contig summary write");
+	}
+
+	process(&params, graph, hp, provenance);
     }
-    duration = time::Instant::now() - begin;
-    eprintln!("\tend {}s{}", duration.as_secs(), duration.subsec_millis());
+}
 
-    
-    eprintln!("write barcode graph\n\tbegin");
-    begin = time::Instant::now();
+/* Molecules and graphs, one by haplotype with its contig summary in split mode, are load from checkpoint when it's possible */
+fn build_graphs(params: &cli::Build, checkpoint: &mut Option<checkpoint::Checkpoint>) -> checkpoint::Graphs {
+    let saved = checkpoint.as_ref().and_then(|c| c.molecules());
+    let Molecules { tig2barcode2premol2pos, tig2len, mut tig2summary, mask, .. } = match saved {
+	Some(molecules) => {
+	    molecule_outputs(&params.input, &molecules);
+	    molecules
+	}
+	None => {
+	    let molecules = molecules(&params.input);
+	    if let Some(checkpoint) = checkpoint.as_mut() {
+		checkpoint.save_molecules(&molecules);
+	    }
 
-    let mut writer = std::io::BufWriter::new(
-	std::fs::File::create(params.output).expect("error opening output file")
-    );
+	    molecules
+	}
+    };
     
-    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").expect("error durring gexf write");
-    writeln!(writer, "<gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">").expect("error durring gexf write");
-    writeln!(writer, "<graph mode=\"static\" defaultedgetype=\"undirected\">").expect("error durring gexf write");
+    let stage = logging::Stage::new("found edge of barcode graph");
+
+    let build_graph = |tig2barcode2premol2pos: &parse_info::Tig2Barcode2Premol2Pos, tig2summary: &mut contig::Tig2Summary| {
+	if params.molecule_graph {
+	    graph::build_molecule(tig2barcode2premol2pos, &tig2len, params.input.threshold, params.weight_model, &mask, tig2summary)
+	} else {
+	    graph::build(tig2barcode2premol2pos, &tig2len, params.input.threshold, params.weight_model, &mask, tig2summary)
+	}
+    };
+
+    let graphs = match params.haplotype {
+	None => {
+	    let graph = build_graph(&tig2barcode2premol2pos, &mut tig2summary);
+	    vec![(None, graph, tig2summary)]
+	}
+	Some(mode) => {
+	    let read2hap = match (&params.haplotag, &params.phased_vcf, &params.read_alleles) {
+		(Some(path), _, _) => haplotype::haplotag(path),
+		(None, Some(vcf), Some(alleles)) => haplotype::read_alleles(alleles, &haplotype::vcf(vcf)),
+		_ => HashMap::new(),
+	    };
+	    let mut tig2barcode2phased = haplotype::Tig2Barcode2Phased::new();
+	    for input in params.input.ema.iter() {
+		let (label, path) = parse_info::library(input);
+		haplotype::reads(&mut tig2barcode2phased, path, label, &read2hap);
+	    }
+	    let tig2barcode2premol2hap = haplotype::molecules(&tig2barcode2premol2pos, &tig2barcode2phased);
+	    let haps = haplotype::haplotypes(&tig2barcode2premol2hap);
+	    log::info!("{} haplotypes found", haps.len());
+
+	    let mut hap_graphs = Vec::new();
+	    let mut hap_summaries = Vec::new();
+	    for hp in haps {
+		let selected = haplotype::select(&tig2barcode2premol2pos, &tig2barcode2premol2hap, hp);
+		let mut hap_summary = contig::subset(&tig2summary, &selected);
+
+		hap_graphs.push((hp, build_graph(&selected, &mut hap_summary)));
+		hap_summaries.push(hap_summary);
+	    }
+
+	    if mode == haplotype::Mode::Split {
+		for (hp, hap_graph) in hap_graphs.iter_mut() {
+		    let label = hp.to_string();
+		    hap_graph.nodes.values_mut().for_each(|n| n.haplotype = Some(label.clone()));
+		    hap_graph.edges.values_mut().for_each(|e| e.haplotype = Some(label.clone()));
+		}
+
+		hap_graphs.into_iter().zip(hap_summaries).map(|((hp, g), s)| (Some(hp), g, s)).collect()
+	    } else {
+		let mut graph = build_graph(&tig2barcode2premol2pos, &mut tig2summary);
+		haplotype::annotate(&mut graph, &tig2barcode2premol2pos, &tig2barcode2premol2hap, &hap_graphs);
 
-    writeln!(writer, "<nodes>").expect("error durring gexf write");
-    for node in nodes {
-	writeln!(writer, "<node id=\"{}\" label=\"{}\" />", node, node).expect("error durring gexf write");
+		vec![(None, graph, tig2summary)]
+	    }
+	}
+    };
+
+    stage.end();
+
+    graphs
+}
+
+/* Filter, prune, detect community and write graph, output paths get haplotype suffix if any */
+fn process(params: &cli::Build, mut graph: graph::Graph, hp: Option<u8>, provenance: &provenance::Provenance) {
+    let output = |path: &str| match hp {
+	Some(hp) => haplotype::path(path, hp),
+	None => path.to_string(),
+    };
+
+    let stage = logging::Stage::new("filter barcode graph");
+
+    if let Some(fdr) = params.fdr {
+	let removed = significance::filter_fdr(&mut graph, fdr);
+	log::info!("{} edges removed at fdr {}", removed, fdr);
     }
-    writeln!(writer, "</nodes>").expect("error durring gexf write");
 
-    writeln!(writer, "<edges>").expect("error durring gexf write");
-    let mut id = 0;
-    for (edge, val) in edges {
-	writeln!(writer, "<edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\" />", id, edge.0, edge.1, val).expect("error durring gexf write");
-	id += 1;
+    for pass in params.prune.iter() {
+	let (nodes, edges) = prune::apply(&mut graph, pass);
+	log::info!("prune {}: {} nodes and {} edges removed", pass, nodes, edges);
     }
+
+    stage.end();
+
+
+    if let Some(method) = params.community {
+	let stage = logging::Stage::new("community detection");
+
+	let modularity = community::graph(&mut graph, method);
+	log::info!("modularity {}", modularity);
+
+	if let Some(path) = &params.cluster_output {
+	    let mut writer = file::get_writable(&output(path));
+	    community::write_clusters(&mut writer, &graph).expect("error durring cluster write");
+	}
+
+	if let Some(path) = &params.neighbourhood_output {
+	    let clusters = community::neighbourhood(&graph, method);
+
+	    let mut writer = file::get_writable(&output(path));
+	    community::write_neighbourhood(&mut writer, &clusters).expect("error durring neighbourhood write");
+	}
+
+	stage.end();
+    }
+
     
-    writeln!(writer, "</edges>").expect("error durring gexf write");
+    let stage = logging::Stage::new("write barcode graph");
+
+    let path = output(&params.output);
+    let mut writer = file::get_compressed_writable(&path, params.compress.unwrap_or_else(|| file::compression_from_extension(&path)));
     
-    writeln!(writer, "</graph>").expect("error durring gexf write");
-    writeln!(writer, "</gexf>").expect("error durring gexf write");
+    gexf::write(&mut writer, &graph, Some(provenance)).expect("error durring gexf write");
     
-    duration = time::Instant::now() - begin;
-    eprintln!("\tend {}s{}", duration.as_secs(), duration.subsec_millis());
+    stage.end();
+}
+
+fn stats(params: cli::Stats, provenance: &provenance::Provenance) {
+    let stage = logging::Stage::new("read barcode graph");
+
+    let reader = std::io::BufReader::new(file::get_readable_file(&params.input).0);
+    let graph = gexf::read(reader).expect("error durring gexf read");
+
+    stage.end();
+
+
+    let stage = logging::Stage::new("compute statistics");
+
+    let stats = statistics::compute(&graph, params.bins);
+
+    if let Some(path) = &params.json {
+	let writer = file::get_writable(path);
+	serde_json::to_writer_pretty(writer, &provenance::Report { provenance, result: &stats }).expect("error durring json write");
+    }
+
+    if let Some(path) = &params.tsv {
+	let mut writer = file::get_writable(path);
+	statistics::write_tsv(&mut writer, &stats).expect("error durring tsv write");
+    } else if params.json.is_none() {
+	statistics::write_tsv(&mut std::io::stdout().lock(), &stats).expect("error durring tsv write");
+    }
+
+    stage.end();
 }
 
-pub fn get_ovl(pos1: (u64, u64), pos2: (u64, u64)) -> Option<u64> {
-    // pos1 contains pos2
-    if pos2.0 > pos1.0 && pos2.1 < pos1.1 {
-	return Some(pos2.1 - pos2.0)
+fn contig_ends(params: cli::Ends) {
+    let Molecules { tig2barcode2premol2pos, tig2len, .. } = molecules(&params.input);
+
+    let stage = logging::Stage::new("link contig ends");
+
+    let profiles = ends::profiles(&tig2barcode2premol2pos, &tig2len, params.window);
+    let links = ends::links(&profiles, params.min_shared);
+    log::info!("{} links between contig ends", links.len());
+
+    if let Some(path) = &params.profiles {
+	let mut writer = file::get_writable(path);
+	ends::write_profiles(&mut writer, &profiles).expect("error durring profiles write");
     }
 
-    // pos2 contains pos1
-    if pos1.0 > pos2.0 && pos1.1 < pos2.1 {
-	return Some(pos1.1 - pos1.0)
+    let mut writer = file::get_writable(&params.output);
+    ends::write_gfa(&mut writer, &tig2len, &profiles, &links).expect("error durring gfa write");
+
+    stage.end();
+}
+
+fn scaffolding(params: cli::Scaffold) {
+    let Molecules { tig2barcode2premol2pos, tig2len, .. } = molecules(&params.input);
+
+    let stage = logging::Stage::new("scaffolding");
+
+    let profiles = ends::profiles(&tig2barcode2premol2pos, &tig2len, params.window);
+    let links = scaffold::select(&ends::links(&profiles, params.min_shared), params.method);
+
+    let molecule_length = scaffold::molecule_length(&tig2barcode2premol2pos);
+    let joins: Vec<scaffold::Join> = links
+	.into_iter()
+	.map(|link| scaffold::Join { gap: scaffold::gap(&profiles, &link, molecule_length, params.min_gap), link })
+	.collect();
+
+    let mut lengths: std::collections::BTreeMap<String, u64> = tig2len.iter().map(|(t, l)| (t.clone(), *l as u64)).collect();
+    for (tig, barcode2premol2pos) in tig2barcode2premol2pos.iter() {
+	lengths.entry(tig.clone()).or_insert_with(|| graph::tig_length(tig, barcode2premol2pos, &tig2len));
     }
 
-    if pos2.1 > pos1.0 && pos2.1 < pos1.1 {
-	return Some(pos2.1 - pos1.0)
+    let scaffolds = scaffold::scaffolds(&lengths, &joins);
+    log::info!("{} joins, {} scaffolds", joins.len(), scaffolds.len());
+
+    let mut writer = file::get_writable(&params.output);
+    scaffold::write_agp(&mut writer, &lengths, &scaffolds).expect("error durring agp write");
+
+    if let Some(path) = &params.gfa {
+	let mut writer = file::get_writable(path);
+	scaffold::write_gfa(&mut writer, &lengths, &scaffolds).expect("error durring gfa write");
     }
 
-    if pos1.1 > pos2.0 && pos1.1 < pos2.1 {
-	return Some(pos1.1 - pos2.0)
+    stage.end();
+}
+
+fn breakpoints(params: cli::Breakpoints) {
+    if let (Some(_), Some(asm)) = (&params.split, &params.input.asm) {
+	if !parse_info::is_fasta(asm) {
+	    log::error!("split assembly need sequences, assembly {} must be a fasta", asm);
+	    std::process::exit(1);
+	}
     }
 
-    return None
+    let Molecules { tig2barcode2premol2pos, tig2len, .. } = molecules(&params.input);
+
+    let stage = logging::Stage::new("detect breakpoints");
+
+    let breakpoints = breakpoint::detect(&tig2barcode2premol2pos, &tig2len, params.window, params.step, params.min_barcodes, params.max_shared);
+    log::info!("{} breakpoints", breakpoints.len());
+
+    let mut writer = file::get_writable(&params.output);
+    breakpoint::write_bed(&mut writer, &breakpoints).expect("error durring bed write");
+
+    if let (Some(path), Some(asm)) = (&params.split, &params.input.asm) {
+	let (reader, _) = file::get_readable_file(asm);
+	let writer = file::get_writable(path);
+	breakpoint::split(reader, writer, &breakpoints).expect("error durring split assembly write");
+    }
+
+    stage.end();
 }
 
+fn merge_graphs(params: cli::Merge, provenance: &provenance::Provenance) {
+    let stage = logging::Stage::new("read barcode graphs");
+
+    let mut graphs = Vec::new();
+    for input in params.inputs.iter() {
+	let (label, path) = parse_info::library(input);
+
+	let reader = std::io::BufReader::new(file::get_readable_file(path).0);
+	let graph = gexf::read(reader).expect("error durring gexf read");
+
+	graphs.push(match label {
+	    Some(label) => merge::namespace(graph, label),
+	    None => graph,
+	});
+    }
+
+    stage.end();
+
+
+    let stage = logging::Stage::new("merge barcode graphs");
+
+    let graph = match merge::merge(graphs, params.method) {
+	Ok(graph) => graph,
+	Err(message) => {
+	    log::error!("{}", message);
+	    std::process::exit(1);
+	}
+    };
+    log::info!("{} nodes and {} edges", graph.nodes.len(), graph.edges.len());
+
+    let mut writer = file::get_compressed_writable(&params.output, params.compress.unwrap_or_else(|| file::compression_from_extension(&params.output)));
+    gexf::write(&mut writer, &graph, Some(provenance)).expect("error durring gexf write");
+
+    stage.end();
+}
+
+fn compare_graphs(params: cli::Compare, provenance: &provenance::Provenance) {
+    let stage = logging::Stage::new("read barcode graphs");
+
+    let reader = std::io::BufReader::new(file::get_readable_file(&params.first).0);
+    let first = gexf::read(reader).expect("error durring gexf read");
+
+    let reader = std::io::BufReader::new(file::get_readable_file(&params.second).0);
+    let second = gexf::read(reader).expect("error durring gexf read");
+
+    stage.end();
+
+
+    let stage = logging::Stage::new("compare barcode graphs");
+
+    let comparison = compare::compare(&first, &second, params.threshold);
+
+    if let Some(path) = &params.json {
+	let writer = file::get_writable(path);
+	serde_json::to_writer_pretty(writer, &provenance::Report { provenance, result: &comparison }).expect("error durring json write");
+    }
+
+    if let Some(path) = &params.tsv {
+	let mut writer = file::get_writable(path);
+	compare::write_tsv(&mut writer, &comparison).expect("error durring tsv write");
+    } else if params.json.is_none() {
+	compare::write_tsv(&mut std::io::stdout().lock(), &comparison).expect("error durring tsv write");
+    }
+
+    if let Some(path) = &params.diff {
+	let mut writer = file::get_writable(path);
+	gexf::write(&mut writer, &compare::diff(&first, &second, params.threshold), Some(provenance)).expect("error durring gexf write");
+    }
+
+    stage.end();
+}
+
+fn simulation(params: cli::Simulate) {
+    let stage = logging::Stage::new("simulate linked reads");
+
+    let tig2len: std::collections::BTreeMap<String, u64> = parse_info::assembly(params.asm.clone()).into_iter().map(|(t, l)| (t, l as u64)).collect();
+
+    let config = simulate::Config {
+	barcodes: params.barcodes,
+	molecules_per_barcode: params.molecules_per_barcode,
+	molecule_length: params.molecule_length,
+	reads_per_molecule: params.reads_per_molecule,
+	read_length: params.read_length,
+	seed: params.seed,
+    };
+    let molecules = simulate::simulate(&tig2len, &config);
+    log::info!("{} molecules and {} reads", molecules.len(), molecules.iter().map(|m| m.reads.len()).sum::<usize>());
+
+    let mut writer = file::get_writable(&params.output);
+    match params.format {
+	simulate::Format::Tsv => simulate::write_tsv(&mut writer, &tig2len, &molecules),
+	simulate::Format::Sam => simulate::write_sam(&mut writer, &tig2len, &molecules, params.read_length),
+    }
+    .expect("error durring simulated mapping write");
+
+    let mut writer = file::get_writable(&params.truth);
+    simulate::write_truth(&mut writer, &molecules).expect("error durring truth write");
+
+    stage.end();
+}
+
+fn evaluation(params: cli::Evaluate, provenance: &provenance::Provenance) {
+    let Molecules { tig2barcode2premol2pos, tig2len, mut tig2summary, mask, .. } = molecules(&params.input);
+
+    let stage = logging::Stage::new("evaluate");
+
+    let truth = simulate::read_truth(&params.truth);
+
+    let graph = match &params.graph {
+	Some(path) => {
+	    let reader = std::io::BufReader::new(file::get_readable_file(path).0);
+	    gexf::read(reader).expect("error durring gexf read")
+	}
+	None => graph::build(&tig2barcode2premol2pos, &tig2len, params.input.threshold, graph::WeightModel::MaxOverlap, &mask, &mut tig2summary),
+    };
+
+    let mut evaluation = evaluate::Evaluation::default();
+    evaluate::molecules(&truth, &tig2barcode2premol2pos, &mut evaluation);
+    evaluate::edges(&truth, &graph, params.input.threshold, &mut evaluation);
+
+    if let Some(path) = &params.json {
+	let writer = file::get_writable(path);
+	serde_json::to_writer_pretty(writer, &provenance::Report { provenance, result: &evaluation }).expect("error durring json write");
+    }
+
+    if let Some(path) = &params.tsv {
+	let mut writer = file::get_writable(path);
+	evaluate::write_tsv(&mut writer, &evaluation).expect("error durring tsv write");
+    } else if params.json.is_none() {
+	evaluate::write_tsv(&mut std::io::stdout().lock(), &evaluation).expect("error durring tsv write");
+    }
+
+    stage.end();
+}
+
+fn querying(params: cli::Query, provenance: &provenance::Provenance) {
+    let stage = logging::Stage::new("read graph or molecule table");
+
+    let graph = params.graph.as_ref().map(|path| {
+	let reader = std::io::BufReader::new(file::get_readable_file(path).0);
+	gexf::read(reader).expect("error durring gexf read")
+    });
+    let tig2barcode2premol2pos = params.molecule_table.as_ref().map(|path| match (&params.region, table::valid_index(path)) {
+	(Some(region), Some(index)) => table::read_region(path, &index, region),
+	_ => table::read(path),
+    }).unwrap_or_default();
+
+    stage.end();
+
+
+    let stage = logging::Stage::new("query");
+
+    let mut writer = file::get_writable(&params.output);
+    let nodes: BTreeSet<String> = match (&params.barcode, &params.region) {
+	(Some(barcode), _) => {
+	    let neighbours = match &graph {
+		Some(graph) => query::graph_neighbours(graph, barcode, params.depth),
+		None => query::table_neighbours(&tig2barcode2premol2pos, barcode, params.depth, params.threshold),
+	    };
+	    log::info!("{} nodes found", neighbours.len());
+
+	    query::write_neighbours(&mut writer, &neighbours).expect("error durring query write");
+	    neighbours.into_iter().map(|n| n.id).collect()
+	}
+	(None, Some(region)) => {
+	    let hits = match &graph {
+		Some(graph) => query::graph_region(graph, region).unwrap_or_else(|message| {
+		    log::error!("{}", message);
+		    std::process::exit(1);
+		}),
+		None => query::table_region(&tig2barcode2premol2pos, region),
+	    };
+	    log::info!("{} molecules found", hits.len());
+
+	    query::write_hits(&mut writer, &hits).expect("error durring query write");
+	    hits.into_iter().map(|h| h.id).collect()
+	}
+	(None, None) => BTreeSet::new(),
+    };
+
+    if let (Some(path), Some(graph)) = (&params.subgraph, &graph) {
+	let mut writer = file::get_writable(path);
+	gexf::write(&mut writer, &query::subgraph(graph, &nodes), Some(provenance)).expect("error durring gexf write");
+    }
+
+    stage.end();
+}
+
+fn indexing(params: cli::Index) {
+    let output = params.output.clone().unwrap_or_else(|| params.input.clone());
+    if file::is_std(&output) || file::compression_from_extension(&output) != file::CompressionFormat::No {
+	log::error!("indexed molecule table {} must be an uncompressed file, set another output with -o", output);
+	std::process::exit(1);
+    }
+
+    let stage = logging::Stage::new("sort molecule table");
+
+    let tig2barcode2premol2pos = table::read(&params.input);
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&output).unwrap_or_else(|e| panic!("Can't create molecule table {}: {}", output, e)));
+    table::write(&mut writer, &tig2barcode2premol2pos).expect("error durring molecule table write");
+    writer.into_inner().map_err(|e| e.into_error()).expect("error durring molecule table write");
+
+    stage.end();
+
+
+    let stage = logging::Stage::new("index molecule table");
+
+    let index = table::index(&output, params.bin.max(1)).unwrap_or_else(|message| {
+	log::error!("{}", message);
+	std::process::exit(1);
+    });
+    log::info!("{} contigs indexed", index.contigs.len());
+
+    let mut writer = file::get_writable(&table::index_path(&output));
+    table::write_index(&mut writer, &index).expect("error durring molecule index write");
+
+    stage.end();
+}
@@ -0,0 +1,206 @@
+/* project use */
+use crate::file;
+use crate::parse_info::Tig2Barcode2Poss;
+
+/* std use */
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+
+/* For each contig sorted and merged intervals, 0-based half-open like bed */
+pub type Mask = BTreeMap<String, Vec<(u64, u64)>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Exclude,
+    DownWeight,
+}
+
+impl Mode {
+    pub const NAMES: &'static [&'static str] = &["exclude", "down-weight"];
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exclude" => Ok(Mode::Exclude),
+            "down-weight" => Ok(Mode::DownWeight),
+            _ => Err(format!("unknow mask mode {}", s)),
+        }
+    }
+}
+
+pub fn read_bed(bed_path: &str) -> Result<Mask, String> {
+    let (reader, _) = file::get_readable_file(bed_path);
+
+    parse_bed(reader).map_err(|message| format!("mask {} {}", bed_path, message))
+}
+
+/* Interval with a begin or an end which isn't a number or an end before begin is an error */
+fn parse_bed<R: Read>(reader: R) -> Result<Mask, String> {
+    let mut mask = Mask::new();
+
+    let mut parser = csv::ReaderBuilder::new().delimiter(b'\t').flexible(true).has_headers(false).comment(Some(b'#')).from_reader(reader);
+    for result in parser.records() {
+        let record = result.map_err(|e| format!("can't be parsed: {}", e))?;
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        if record.len() < 3 || record[0].starts_with("track") || record[0].starts_with("browser") {
+            continue;
+        }
+
+        let begin = record[1].parse::<u64>().map_err(|_| format!("line {}: begin {} isn't a number", line, &record[1]))?;
+        let end = record[2].parse::<u64>().map_err(|_| format!("line {}: end {} isn't a number", line, &record[2]))?;
+        if end < begin {
+            return Err(format!("line {}: end {} is before begin {}", line, end, begin));
+        }
+        mask.entry(record[0].to_string()).or_default().push((begin, end));
+    }
+
+    merge(&mut mask);
+
+    Ok(mask)
+}
+
+/* Mask windows where number of barcode is upper than fold time the median of non empty windows */
+pub fn auto(tig2barcode2poss: &Tig2Barcode2Poss, window: u64, fold: f64) -> Mask {
+    let mut tig2counts: HashMap<&String, BTreeMap<u64, usize>> = HashMap::new();
+
+    for (tig, barcode2poss) in tig2barcode2poss {
+        let mut window2barcodes: BTreeMap<u64, HashSet<&String>> = BTreeMap::new();
+        for (barcode, poss) in barcode2poss {
+            for pos in poss {
+                window2barcodes.entry(pos.saturating_sub(1) / window).or_default().insert(barcode);
+            }
+        }
+
+        tig2counts.insert(tig, window2barcodes.into_iter().map(|(w, b)| (w, b.len())).collect());
+    }
+
+    let mut counts: Vec<usize> = tig2counts.values().flat_map(|c| c.values().cloned()).collect();
+    if counts.is_empty() {
+        return Mask::new();
+    }
+    counts.sort_unstable();
+    let limit = counts[counts.len() / 2] as f64 * fold;
+
+    let mut mask = Mask::new();
+    for (tig, window2count) in tig2counts {
+        for (w, count) in window2count {
+            if count as f64 > limit {
+                mask.entry(tig.clone()).or_default().push((w * window, (w + 1) * window));
+            }
+        }
+    }
+
+    merge(&mut mask);
+
+    mask
+}
+
+pub fn extend(mask: &mut Mask, other: Mask) {
+    for (tig, intervals) in other {
+        mask.entry(tig).or_default().extend(intervals);
+    }
+
+    merge(mask);
+}
+
+/* Remove mapping position, 1-based, in masked region */
+pub fn exclude(tig2barcode2poss: &mut Tig2Barcode2Poss, mask: &Mask) -> usize {
+    let mut removed = 0;
+
+    for (tig, barcode2poss) in tig2barcode2poss.iter_mut() {
+        if let Some(intervals) = mask.get(tig) {
+            for poss in barcode2poss.values_mut() {
+                let before = poss.len();
+                poss.retain(|pos| masked_length(intervals, pos.saturating_sub(1), *pos) == 0);
+                removed += before - poss.len();
+            }
+        }
+    }
+
+    removed
+}
+
+/* Number of masked bases in [begin, end) */
+pub fn masked_length(intervals: &[(u64, u64)], begin: u64, end: u64) -> u64 {
+    let first = intervals.partition_point(|(_, e)| *e <= begin);
+
+    intervals[first..]
+        .iter()
+        .take_while(|(b, _)| *b < end)
+        .map(|(b, e)| e.min(&end) - b.max(&begin))
+        .sum()
+}
+
+pub fn write_bed<W: Write>(writer: &mut W, mask: &Mask) -> std::io::Result<()> {
+    for (tig, intervals) in mask.iter() {
+        for (begin, end) in intervals {
+            writeln!(writer, "{}\t{}\t{}", tig, begin, end)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn merge(mask: &mut Mask) {
+    for intervals in mask.values_mut() {
+        intervals.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(intervals.len());
+        for (begin, end) in intervals.drain(..) {
+            match merged.last_mut() {
+                Some(last) if begin <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((begin, end)),
+            }
+        }
+
+        *intervals = merged;
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn masked_bases() {
+        let mut mask = Mask::new();
+        mask.insert("tig1".to_string(), vec![(100, 200), (150, 300), (500, 600)]);
+        merge(&mut mask);
+
+        assert_eq!(mask["tig1"], vec![(100, 300), (500, 600)]);
+        assert_eq!(masked_length(&mask["tig1"], 0, 100), 0);
+        assert_eq!(masked_length(&mask["tig1"], 250, 550), 100);
+        assert_eq!(masked_length(&mask["tig1"], 0, 1000), 300);
+    }
+
+    #[test]
+    fn bed_lines() {
+        let mask = parse_bed(&b"track name=mask\ntig1\t100\t200\n# comment\ntig1\t150\t300\ttig2\n"[..]).unwrap();
+        assert_eq!(mask["tig1"], vec![(100, 300)]);
+
+        assert_eq!(parse_bed(&b"tig1\t100\t200\ntig1\t300\t250\n"[..]), Err("line 2: end 250 is before begin 300".to_string()));
+        assert_eq!(parse_bed(&b"tig1\t100\tend\n"[..]), Err("line 1: end end isn't a number".to_string()));
+    }
+
+    #[test]
+    fn auto_mask() {
+        let mut tig2barcode2poss = Tig2Barcode2Poss::new();
+        let barcode2poss = tig2barcode2poss.entry("tig1".to_string()).or_default();
+        for b in 0..10 {
+            barcode2poss.insert(format!("BC{}", b), vec![b * 1000 + 1, 5500]);
+        }
+
+        let mask = auto(&tig2barcode2poss, 1000, 3.0);
+
+        assert_eq!(mask["tig1"], vec![(5000, 6000)]);
+
+        exclude(&mut tig2barcode2poss, &mask);
+        assert_eq!(tig2barcode2poss["tig1"]["BC5"], Vec::<u64>::new());
+        assert_eq!(tig2barcode2poss["tig1"]["BC1"], vec![1001]);
+    }
+}
@@ -0,0 +1,170 @@
+/* project use */
+use crate::graph::{Edge, Graph, Node};
+use crate::significance;
+
+/* std use */
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Sum,
+    Max,
+}
+
+impl Method {
+    pub const NAMES: &'static [&'static str] = &["sum", "max"];
+}
+
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Method::Sum),
+            "max" => Ok(Method::Max),
+            _ => Err(format!("unknow merge method {}", s)),
+        }
+    }
+}
+
+/* Prefix node ids and barcodes by library label, like barcodes of a labelled ema input */
+pub fn namespace(graph: Graph, label: &str) -> Graph {
+    let prefix = |id: &str| format!("{}:{}", label, id);
+
+    Graph {
+        model: graph.model,
+        nodes: graph
+            .nodes
+            .into_iter()
+            .map(|(id, mut node)| {
+                node.barcode = node.barcode.map(|b| prefix(&b));
+                (prefix(&id), node)
+            })
+            .collect(),
+        edges: graph.edges.into_iter().map(|((node1, node2), edge)| ((prefix(&node1), prefix(&node2)), edge)).collect(),
+    }
+}
+
+/* Sum add evidence of graphs, except number of contigs which is the maximum as contigs are only count and graphs of an assembly share them, max keep the strongest evidence of each graph, p-value and weight are recomputed from merged evidence */
+pub fn merge(graphs: Vec<Graph>, method: Method) -> Result<Graph, String> {
+    let model = match graphs.first() {
+        Some(graph) => graph.model,
+        None => return Err("no graph to merge".to_string()),
+    };
+    if let Some(graph) = graphs.iter().find(|g| g.model != model) {
+        return Err(format!("graphs use different weight models, {} and {}", model, graph.model));
+    }
+
+    let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+    let mut edges: BTreeMap<(String, String), Edge> = BTreeMap::new();
+    for graph in graphs {
+        for (id, node) in graph.nodes {
+            match nodes.get_mut(&id) {
+                Some(merged) => merge_node(merged, node, method),
+                None => {
+                    nodes.insert(id, Node { cluster: None, ..node });
+                }
+            }
+        }
+
+        for (key, edge) in graph.edges {
+            match edges.get_mut(&key) {
+                Some(merged) => merge_edge(merged, edge, method),
+                None => {
+                    edges.insert(key, edge);
+                }
+            }
+        }
+    }
+
+    for edge in edges.values_mut() {
+        edge.pvalue = significance::poisson_sf(edge.pairs, edge.expected_pairs);
+    }
+
+    let mut graph = Graph { model, nodes, edges };
+    graph.set_weight(model);
+
+    Ok(graph)
+}
+
+fn merge_node(merged: &mut Node, node: Node, method: Method) {
+    match method {
+        Method::Sum => {
+            merged.molecules += node.molecules;
+            merged.covered += node.covered;
+        }
+        Method::Max => {
+            merged.molecules = merged.molecules.max(node.molecules);
+            merged.covered = merged.covered.max(node.covered);
+        }
+    }
+
+    if merged.haplotype != node.haplotype {
+        merged.haplotype = None;
+    }
+}
+
+fn merge_edge(merged: &mut Edge, edge: Edge, method: Method) {
+    merged.max_ovl = merged.max_ovl.max(edge.max_ovl);
+
+    match method {
+        Method::Sum => {
+            merged.sum_ovl += edge.sum_ovl;
+            merged.pairs += edge.pairs;
+            merged.contigs = merged.contigs.max(edge.contigs);
+            merged.expected_pairs += edge.expected_pairs;
+            merged.expected_overlap += edge.expected_overlap;
+        }
+        Method::Max => {
+            merged.sum_ovl = merged.sum_ovl.max(edge.sum_ovl);
+            merged.pairs = merged.pairs.max(edge.pairs);
+            merged.contigs = merged.contigs.max(edge.contigs);
+            merged.expected_pairs = merged.expected_pairs.max(edge.expected_pairs);
+            merged.expected_overlap = merged.expected_overlap.max(edge.expected_overlap);
+        }
+    }
+
+    if merged.haplotype != edge.haplotype {
+        merged.haplotype = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::{key, WeightModel};
+
+    fn graph(pairs: u64, sum_ovl: u64) -> Graph {
+        let mut graph = Graph::default();
+        for name in &["A", "B"] {
+            graph.nodes.insert(name.to_string(), Node { molecules: 1, covered: 20000, ..Default::default() });
+        }
+        graph.edges.insert(key("A", "B"), Edge { max_ovl: sum_ovl, sum_ovl, pairs, contigs: 1, ..Default::default() });
+        graph.set_weight(WeightModel::SumOverlap);
+
+        graph
+    }
+
+    #[test]
+    fn sum_and_max() {
+        let merged = merge(vec![graph(1, 10000), graph(2, 15000)], Method::Sum).unwrap();
+        assert_eq!(merged.nodes["A"].molecules, 2);
+        assert_eq!(merged.edges[&key("A", "B")].pairs, 3);
+        assert_eq!(merged.edges[&key("A", "B")].weight, 25000.0);
+        assert_eq!(merged.edges[&key("A", "B")].contigs, 1);
+
+        let merged = merge(vec![graph(1, 10000), graph(2, 15000)], Method::Max).unwrap();
+        assert_eq!(merged.nodes["A"].molecules, 1);
+        assert_eq!(merged.edges[&key("A", "B")].weight, 15000.0);
+
+        let merged = merge(vec![namespace(graph(1, 10000), "lib1"), namespace(graph(2, 15000), "lib2")], Method::Sum).unwrap();
+        assert_eq!(merged.nodes.len(), 4);
+        assert!(merged.edges.contains_key(&key("lib2:A", "lib2:B")));
+
+        let mut other = graph(1, 10000);
+        other.set_weight(WeightModel::Pairs);
+        assert!(merge(vec![graph(1, 10000), other], Method::Sum).is_err());
+    }
+}
@@ -1,34 +1,71 @@
 /* project use */
-use file;
+use crate::file;
+use crate::logging;
 
 /* std use */
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
 
-/* crates use */
-use csv;
+pub type Tig2Barcode2Poss = HashMap<String, HashMap<String, Vec<u64>>>;
+pub type Tig2Barcode2Premol2Pos = HashMap<String, HashMap<String, Vec<(u64, u64)>>>;
 
-pub fn ema(tsv_path: String, premolecule_threshold: u64, ovl_threshold: u64) -> HashMap<String, HashMap<String, Vec<(u64, u64)>>> {
+/* Input can be prefixed by a library label, label=path, if path isn't an existing file */
+pub fn library(input: &str) -> (Option<&str>, &str) {
+    match input.find('=') {
+        Some(index) if !std::path::Path::new(input).exists() => (Some(&input[..index]), &input[index + 1..]),
+        _ => (None, input),
+    }
+}
 
-    let mut tig2barcode2poss: HashMap<String, HashMap<String, Vec<u64>>> = HashMap::new();
+/* Return also contig length found in @SQ header lines, barcodes of a labelled library are namespaced as label:barcode */
+pub fn ema(tsv_path: String, label: Option<&str>) -> (Tig2Barcode2Poss, HashMap<String, usize>) {
 
-    let (reader, _) = niffler::from_path(&tsv_path).expect("ema file opening");
+    let mut tig2barcode2poss: Tig2Barcode2Poss = HashMap::new();
+    let mut tig2len: HashMap<String, usize> = HashMap::new();
 
+    let (reader, _) = file::get_readable_file(&tsv_path);
+
+    let mut progress = logging::Progress::new("ema records parsed", 10_000_000, None);
     let mut parser = csv::ReaderBuilder::new().delimiter(b'\t').flexible(true).has_headers(false).from_reader(reader);
     for result in parser.records() {
         let record = result.expect("Error during ema parsing");
+        progress.inc();
+
+        /* Header lines, only @SQ are used */
+        if record[0].starts_with('@') {
+            if &record[0] == "@SQ" {
+                if let Some((tig, len)) = sq_line(record.iter()) {
+                    tig2len.insert(tig, len);
+                }
+            }
+            continue;
+        }
 
-        if record.len() != 5 {
+        /* Columns after the five of ema, as HP and PS tags, are read only in haplotype mode */
+        if record.len() < 5 {
             continue;
-        } 
+        }
         
         let tig_id = record[1].to_string();
         let pos = record[2].parse::<u64>().unwrap();
-        let barcode_id = record[3].to_string().split('-').next().unwrap().to_string();
+        let barcode_id = namespace(record[3].split('-').next().unwrap(), label);
 
-        tig2barcode2poss.entry(tig_id.clone()).or_insert(HashMap::new()).entry(barcode_id.clone()).or_insert(Vec::new()).push(pos);
+        tig2barcode2poss.entry(tig_id).or_default().entry(barcode_id).or_default().push(pos);
     }
+    log::debug!("{} records read from {}", progress.count(), tsv_path);
+
+    (tig2barcode2poss, tig2len)
+}
 
-    let mut tig2barcode2premol2pos: HashMap<String, HashMap<String, Vec<(u64, u64)>>> = HashMap::new();
+pub fn namespace(barcode: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{}:{}", label, barcode),
+        None => barcode.to_string(),
+    }
+}
+
+pub fn premolecule(tig2barcode2poss: Tig2Barcode2Poss, premolecule_threshold: u64, ovl_threshold: u64) -> Tig2Barcode2Premol2Pos {
+    let mut tig2barcode2premol2pos: Tig2Barcode2Premol2Pos = HashMap::new();
 
     for (tig, value) in tig2barcode2poss {
 	for (barcode, mut poss) in value {
@@ -43,9 +80,8 @@ pub fn ema(tsv_path: String, premolecule_threshold: u64, ovl_threshold: u64) ->
 	    let mut iter = poss.iter();
 	    let mut min = iter.next().unwrap();
 	    let mut prev = min;
-	    let mut last = min;
 	
-	    while let Some(next) = iter.next() {
+	    for next in iter {
 		if next - prev > premolecule_threshold {
 		    if *prev - *min > ovl_threshold {
 			intervals.push((*min, *prev));
@@ -55,25 +91,60 @@ pub fn ema(tsv_path: String, premolecule_threshold: u64, ovl_threshold: u64) ->
 		}
 
 		prev = next;
-		last = next;
-		
 	    }
 	    if *prev - *min > ovl_threshold {	
 		intervals.push((*min, *prev));
 	    }
 	    
-	    tig2barcode2premol2pos.entry(tig.clone()).or_insert(HashMap::new()).insert(barcode.clone(), intervals);
+	    tig2barcode2premol2pos.entry(tig.clone()).or_default().insert(barcode, intervals);
 	}
     }
-    
-    return tig2barcode2premol2pos;
+
+    tig2barcode2premol2pos
 }
 
 
+/* Contig length is read from fasta index, sequence dictionary, sam header or gfa, fasta is parse only if nothing else is available */
 pub fn assembly(asm_path: String) -> HashMap<String, usize> {
+    let index_path = format!("{}.fai", asm_path);
+    if !is_index(&asm_path) && std::path::Path::new(&index_path).exists() {
+        return assembly(index_path);
+    }
+
+    let (reader, _) = file::get_readable_file(&asm_path);
+
+    let name = without_compression(&asm_path);
+    if name.ends_with(".fai") {
+        fai(reader)
+    } else if name.ends_with(".dict") || name.ends_with(".sam") {
+        sam_header(reader)
+    } else if name.ends_with(".gfa") {
+        gfa(reader)
+    } else {
+        fasta(reader)
+    }
+}
+
+fn is_index(path: &str) -> bool {
+    [".fai", ".dict", ".sam", ".gfa"].iter().any(|ext| path.ends_with(ext))
+}
+
+/* Extensions of compressed files are the ones used to choose output compression */
+fn without_compression(path: &str) -> &str {
+    match file::compression_from_extension(path) {
+        file::CompressionFormat::No => path,
+        _ => path.rsplit_once('.').map_or(path, |(name, _)| name),
+    }
+}
+
+/* Contig length sources which aren't a fasta index, dictionary, sam or gfa are read as fasta */
+pub fn is_fasta(path: &str) -> bool {
+    !is_index(without_compression(path))
+}
+
+pub fn fasta<R: Read>(reader: R) -> HashMap<String, usize> {
     let mut tig2len: HashMap<String, usize> = HashMap::new();
 
-    let (reader, _) = niffler::from_path(asm_path).expect("assembly file opening");
     let mut records = bio::io::fasta::Reader::new(reader).records();
 
     while let Some(Ok(record)) = records.next() {
@@ -82,3 +153,131 @@ pub fn assembly(asm_path: String) -> HashMap<String, usize> {
 
     tig2len
 }
+
+pub fn fai<R: Read>(reader: R) -> HashMap<String, usize> {
+    let mut tig2len: HashMap<String, usize> = HashMap::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during fasta index parsing");
+        let mut fields = line.split('\t');
+
+        if let (Some(tig), Some(len)) = (fields.next(), fields.next()) {
+            tig2len.insert(tig.to_string(), len.parse().expect("Error during fasta index parsing"));
+        }
+    }
+
+    tig2len
+}
+
+pub fn sam_header<R: Read>(reader: R) -> HashMap<String, usize> {
+    let mut tig2len: HashMap<String, usize> = HashMap::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during sequence dictionary parsing");
+
+        if !line.starts_with('@') {
+            break;
+        }
+
+        if line.starts_with("@SQ\t") {
+            if let Some((tig, len)) = sq_line(line.split('\t')) {
+                tig2len.insert(tig, len);
+            }
+        }
+    }
+
+    tig2len
+}
+
+pub fn gfa<R: Read>(reader: R) -> HashMap<String, usize> {
+    let mut tig2len: HashMap<String, usize> = HashMap::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during gfa parsing");
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        if fields.len() < 3 || fields[0] != "S" {
+            continue;
+        }
+
+        let len = match fields[3..].iter().find_map(|f| f.strip_prefix("LN:i:")) {
+            Some(value) => value.parse().expect("Error during gfa parsing"),
+            None if fields[2] == "*" => continue,
+            None => fields[2].len(),
+        };
+
+        tig2len.insert(fields[1].to_string(), len);
+    }
+
+    tig2len
+}
+
+fn sq_line<'a, I: Iterator<Item = &'a str>>(fields: I) -> Option<(String, usize)> {
+    let mut tig = None;
+    let mut len = None;
+
+    for field in fields {
+        if let Some(name) = field.strip_prefix("SN:") {
+            tig = Some(name.to_string());
+        } else if let Some(value) = field.strip_prefix("LN:") {
+            len = value.parse().ok();
+        }
+    }
+
+    Some((tig?, len?))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn contig_length() {
+        let fai_file: &[u8] = b"tig1\t1000\t6\t60\t61\ntig2\t25\t1030\t60\t61\n";
+        let dict_file: &[u8] = b"@HD\tVN:1.5\n@SQ\tSN:tig1\tLN:1000\tM5:aaa\n@SQ\tSN:tig2\tLN:25\n";
+        let gfa_file: &[u8] = b"H\tVN:Z:1.0\nS\ttig1\t*\tLN:i:1000\nS\ttig2\tACGTACGTACGTACGTACGTACGTA\nL\ttig1\t+\ttig2\t+\t0M\n";
+        let fasta_file: &[u8] = b">tig1\nAAAA\n>tig2 comment\nACGTACGTACGTACGTACGTACGTA\n";
+
+        let mut expected = HashMap::new();
+        expected.insert("tig1".to_string(), 1000);
+        expected.insert("tig2".to_string(), 25);
+
+        assert_eq!(fai(fai_file), expected);
+        assert_eq!(sam_header(dict_file), expected);
+        assert_eq!(gfa(gfa_file), expected);
+
+        expected.insert("tig1".to_string(), 4);
+        assert_eq!(fasta(fasta_file), expected);
+    }
+
+    #[test]
+    fn fasta_path() {
+        for path in &["asm.fasta", "asm.fa.gz", "asm.fasta.zst"] {
+            assert!(is_fasta(path), "{}", path);
+        }
+        for path in &["asm.fai", "asm.fai.zst", "asm.gfa.zst", "asm.dict.bz2", "asm.sam.xz", "asm.gfa.gz"] {
+            assert!(!is_fasta(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn library_label() {
+        assert_eq!(library("lib1=path/ema.tsv"), (Some("lib1"), "path/ema.tsv"));
+        assert_eq!(library("path/ema.tsv"), (None, "path/ema.tsv"));
+        assert_eq!(namespace("AAAC", library("lib1=ema.tsv").0), "lib1:AAAC");
+    }
+
+    #[test]
+    fn header_lines() {
+        let path = std::env::temp_dir().join(format!("mapping2barcodegraph-header-{}.tsv", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        std::fs::write(&path, "@SQ\tSN:tig1\tLN:1000\n@PG\tID:ema\tPN:ema\tVN:0.6\tCL:ema align\nr1\ttig1\t10\tAAAC-1\t1\tHP:i:1\tPS:i:5\n").unwrap();
+
+        let (tig2barcode2poss, tig2len) = ema(path.clone(), None);
+        assert_eq!(tig2barcode2poss["tig1"]["AAAC"], vec![10]);
+        assert_eq!(tig2len["tig1"], 1000);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
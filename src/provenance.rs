@@ -0,0 +1,150 @@
+/* project use */
+use crate::cli;
+use crate::file;
+use crate::parse_info;
+
+/* crates use */
+use serde::{Deserialize, Serialize};
+use structopt::clap::ArgMatches;
+
+/* std use */
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/* Options which are path of input files */
+const INPUTS: &[&str] = &["ema", "asm", "mask", "haplotag", "input", "inputs", "first", "second", "graph", "molecules", "phased-vcf", "read-alleles"];
+
+/* Options which are path of output files */
+const OUTPUTS: &[&str] = &["output", "contig-summary", "mask-output", "molecule-table", "cluster-output", "neighbourhood-output", "checkpoint-dir", "json", "tsv", "profiles", "gfa", "split", "diff", "truth", "subgraph"];
+
+/* Options which change only log messages */
+const LOGGING: &[&str] = &["verbose", "quiet", "log-format"];
+
+/* Truth is an output of simulate and molecule table an output of commands which infer molecules */
+fn is_input(command: &str, name: &str) -> bool {
+    INPUTS.contains(&name) || (command == "evaluate" && name == "truth") || (command == "query" && name == "molecule-table")
+}
+
+/* Paths of outputs and log options don't change content of outputs, they aren't record so same run write same bytes */
+fn is_recorded(command: &str, name: &str) -> bool {
+    is_input(command, name) || !(OUTPUTS.contains(&name) || LOGGING.contains(&name))
+}
+
+/* Input is identify by its content, standard input has no size and no checksum */
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Input {
+    pub size: Option<u64>,
+    pub md5: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Provenance {
+    pub version: String,
+    pub command: String,
+    pub parameters: BTreeMap<String, Vec<String>>,
+    pub inputs: BTreeMap<String, Input>,
+}
+
+/* A report is a result with provenance of run which produce it */
+#[derive(Debug, Serialize)]
+pub struct Report<'a, T: Serialize> {
+    pub provenance: &'a Provenance,
+    #[serde(flatten)]
+    pub result: &'a T,
+}
+
+impl Provenance {
+    /* Parameters are options set by command line, config or default value, flags are present only if set, inputs are read to compute their checksum */
+    pub fn new(matches: &ArgMatches) -> Self {
+        let mut parameters = BTreeMap::new();
+        let mut inputs = BTreeMap::new();
+        if let Some(path) = matches.value_of("config") {
+            parameters.insert("config".to_string(), vec![path.to_string()]);
+            inputs.insert(path.to_string(), input(path));
+        }
+
+        let (command, matches) = match matches.subcommand() {
+            (command, Some(matches)) => (command, matches),
+            (command, None) => return Provenance { version: env!("CARGO_PKG_VERSION").to_string(), command: command.to_string(), parameters, inputs },
+        };
+
+        for name in cli::SubCommand::args(command) {
+            if !matches.is_present(name) || !is_recorded(command, name) {
+                continue;
+            }
+
+            let values = matches.values_of_lossy(name).filter(|values| !values.is_empty()).unwrap_or_else(|| vec!["true".to_string()]);
+            if is_input(command, name) {
+                for value in values.iter() {
+                    let path = if name == "inputs" || name == "ema" { parse_info::library(value).1 } else { value };
+                    inputs.insert(path.to_string(), input(path));
+                }
+            }
+
+            parameters.insert(name.to_string(), values);
+        }
+
+        Provenance {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            command: command.to_string(),
+            parameters,
+            inputs,
+        }
+    }
+}
+
+fn input(path: &str) -> Input {
+    let metadata = if file::is_std(path) { None } else { std::fs::metadata(path).ok() };
+
+    Input {
+        size: metadata.map(|m| m.len()),
+        md5: md5sum(path),
+    }
+}
+
+/* Checksum of file as store on disk, standard input can't be read twice so it has no checksum */
+pub fn md5sum(path: &str) -> Option<String> {
+    if file::is_std(path) {
+        return None;
+    }
+
+    let mut reader = file::get_readable(path);
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        let read = reader.read(&mut buffer).unwrap_or_else(|e| panic!("Can't read input file {}: {}", path, e));
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+    }
+
+    Some(format!("{:x}", context.compute()))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use structopt::StructOpt;
+
+    #[test]
+    fn from_matches() {
+        let ema = format!("{}/tests/data/ema.tsv", env!("CARGO_MANIFEST_DIR"));
+        let matches = cli::Command::clap().get_matches_from(vec!["m2b", "-v", "build", "-e", &format!("lib1={}", ema), "-o", "graph.gexf", "--molecule-graph"]);
+        let provenance = Provenance::new(&matches);
+
+        assert_eq!(provenance.command, "build");
+        assert_eq!(provenance.parameters["threshold"], vec!["9000"]);
+        assert_eq!(provenance.parameters["molecule-graph"], vec!["true"]);
+        assert_eq!(provenance.inputs.keys().collect::<Vec<_>>(), vec![&ema]);
+        assert_eq!(provenance.inputs[&ema].size, std::fs::metadata(&ema).ok().map(|m| m.len()));
+        assert_eq!(provenance.inputs[&ema].md5.as_ref().map(|s| s.len()), Some(32));
+
+        // output path and log level aren't record
+        for name in &["output", "verbose", "log-format"] {
+            assert!(!provenance.parameters.contains_key(*name));
+        }
+    }
+}
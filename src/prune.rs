@@ -0,0 +1,271 @@
+/* project use */
+use crate::graph::{self, Graph};
+
+/* std use */
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pass {
+    HubDegree(f64),
+    HubMolecules(f64),
+    TopK(usize),
+    RelativeWeight(f64),
+    Isolated,
+    MinComponent(usize),
+    SpanningForest,
+}
+
+impl Pass {
+    pub const HELP: &'static str = "pruning pass apply in order: hub-degree:<percentile>, hub-molecules:<percentile>, top-k:<k>, relative-weight:<ratio>, isolated, min-component:<size>, spanning-forest";
+}
+
+impl std::str::FromStr for Pass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(2, ':');
+        let name = split.next().unwrap_or("");
+        let value = split.next();
+
+        let number = |value: Option<&str>| -> Result<f64, String> {
+            value
+                .ok_or_else(|| format!("pruning pass {} require a value", name))?
+                .parse::<f64>()
+                .map_err(|_| format!("pruning pass {} value isn't a number", name))
+        };
+        let integer = |value: Option<&str>| -> Result<usize, String> {
+            value
+                .ok_or_else(|| format!("pruning pass {} require a value", name))?
+                .parse::<usize>()
+                .map_err(|_| format!("pruning pass {} value isn't a positive integer", name))
+        };
+
+        match name {
+            "hub-degree" => Ok(Pass::HubDegree(number(value)?)),
+            "hub-molecules" => Ok(Pass::HubMolecules(number(value)?)),
+            "top-k" => Ok(Pass::TopK(integer(value)?)),
+            "relative-weight" => Ok(Pass::RelativeWeight(number(value)?)),
+            "isolated" => Ok(Pass::Isolated),
+            "min-component" => Ok(Pass::MinComponent(integer(value)?)),
+            "spanning-forest" => Ok(Pass::SpanningForest),
+            _ => Err(format!("unknow pruning pass {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Pass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Pass::HubDegree(p) => write!(f, "hub-degree:{}", p),
+            Pass::HubMolecules(p) => write!(f, "hub-molecules:{}", p),
+            Pass::TopK(k) => write!(f, "top-k:{}", k),
+            Pass::RelativeWeight(r) => write!(f, "relative-weight:{}", r),
+            Pass::Isolated => write!(f, "isolated"),
+            Pass::MinComponent(s) => write!(f, "min-component:{}", s),
+            Pass::SpanningForest => write!(f, "spanning-forest"),
+        }
+    }
+}
+
+/* Return number of nodes and edges removed, removed ids are log at debug level */
+pub fn apply(graph: &mut Graph, pass: &Pass) -> (usize, usize) {
+    let nb_nodes = graph.nodes.len();
+    let nb_edges = graph.edges.len();
+
+    let before = if log::log_enabled!(log::Level::Debug) {
+        Some((graph.nodes.keys().cloned().collect::<BTreeSet<String>>(), graph.edges.keys().cloned().collect::<BTreeSet<(String, String)>>()))
+    } else {
+        None
+    };
+
+    match pass {
+        Pass::HubDegree(percentile) => {
+            let mut degree: HashMap<String, u64> = graph.nodes.keys().map(|n| (n.clone(), 0)).collect();
+            for (node1, node2) in graph.edges.keys() {
+                *degree.get_mut(node1).unwrap() += 1;
+                *degree.get_mut(node2).unwrap() += 1;
+            }
+
+            remove_hub(graph, &degree, *percentile);
+        }
+        Pass::HubMolecules(percentile) => {
+            let molecules: HashMap<String, u64> = graph.nodes.iter().map(|(n, a)| (n.clone(), a.molecules)).collect();
+
+            remove_hub(graph, &molecules, *percentile);
+        }
+        Pass::TopK(k) => {
+            let (names, neighbours) = graph.adjacency();
+
+            let mut keep = BTreeSet::new();
+            for (i, around) in neighbours.iter().enumerate() {
+                let mut around = around.clone();
+                around.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+
+                for (j, _) in around.iter().take(*k) {
+                    keep.insert(graph::key(names[i], names[*j]));
+                }
+            }
+
+            graph.edges.retain(|k, _| keep.contains(k));
+        }
+        Pass::RelativeWeight(ratio) => {
+            let mut max_weight: HashMap<&String, f64> = HashMap::new();
+            for ((node1, node2), edge) in graph.edges.iter() {
+                for node in &[node1, node2] {
+                    let max = max_weight.entry(node).or_insert(edge.weight);
+                    *max = max.max(edge.weight);
+                }
+            }
+
+            // an edge is removed only if it's weak for both its extremities
+            let remove: BTreeSet<(String, String)> = graph
+                .edges
+                .iter()
+                .filter(|((node1, node2), edge)| edge.weight < ratio * max_weight[node1] && edge.weight < ratio * max_weight[node2])
+                .map(|(k, _)| k.clone())
+                .collect();
+
+            graph.edges.retain(|k, _| !remove.contains(k));
+        }
+        Pass::Isolated => {
+            graph.remove_isolated();
+        }
+        Pass::MinComponent(size) => {
+            let (names, neighbours) = graph.adjacency();
+            let component = graph::components(&neighbours);
+
+            let mut component_size = HashMap::new();
+            for c in component.iter() {
+                *component_size.entry(*c).or_insert(0) += 1;
+            }
+
+            let remove: BTreeSet<String> = names
+                .iter()
+                .zip(component.iter())
+                .filter(|(_, c)| component_size[*c] < *size)
+                .map(|(n, _)| n.to_string())
+                .collect();
+
+            remove_nodes(graph, &remove);
+        }
+        Pass::SpanningForest => {
+            spanning_forest(graph);
+        }
+    }
+
+    if let Some((nodes, edges)) = before {
+        for node in nodes.iter().filter(|n| !graph.nodes.contains_key(*n)) {
+            log::debug!("prune {}: node {} removed", pass, node);
+        }
+        for (node1, node2) in edges.iter().filter(|k| !graph.edges.contains_key(*k)) {
+            log::debug!("prune {}: edge {} {} removed", pass, node1, node2);
+        }
+    }
+
+    (nb_nodes - graph.nodes.len(), nb_edges - graph.edges.len())
+}
+
+/* Remove node with value upper than the value at percentile, by nearest rank */
+fn remove_hub(graph: &mut Graph, node2value: &HashMap<String, u64>, percentile: f64) {
+    let mut values: Vec<u64> = node2value.values().cloned().collect();
+    if values.is_empty() {
+        return;
+    }
+    values.sort_unstable();
+
+    let rank = ((percentile / 100.0 * values.len() as f64).ceil() as usize).clamp(1, values.len());
+    let limit = values[rank - 1];
+
+    let remove: BTreeSet<String> = node2value.iter().filter(|(_, v)| **v > limit).map(|(n, _)| n.clone()).collect();
+
+    remove_nodes(graph, &remove);
+}
+
+fn remove_nodes(graph: &mut Graph, remove: &BTreeSet<String>) {
+    graph.nodes.retain(|n, _| !remove.contains(n));
+    graph.edges.retain(|(node1, node2), _| !remove.contains(node1) && !remove.contains(node2));
+}
+
+/* Kruskal algorithm on edges sorted by decreasing weight */
+fn spanning_forest(graph: &mut Graph) {
+    let index: HashMap<String, usize> = graph.nodes.keys().enumerate().map(|(i, n)| (n.clone(), i)).collect();
+    let mut parent: Vec<usize> = (0..index.len()).collect();
+
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut edges: Vec<((String, String), f64)> = graph.edges.iter().map(|(k, e)| (k.clone(), e.weight)).collect();
+    edges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+
+    let mut keep = BTreeSet::new();
+    for ((node1, node2), _) in edges {
+        let root1 = find(&mut parent, index[&node1]);
+        let root2 = find(&mut parent, index[&node2]);
+
+        if root1 != root2 {
+            parent[root1] = root2;
+            keep.insert((node1, node2));
+        }
+    }
+
+    graph.edges.retain(|k, _| keep.contains(k));
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::{key, Edge, Node};
+
+    /* star centered on A plus a heavy B-C edge and a lone D-E edge */
+    fn star() -> Graph {
+        let mut graph = Graph::default();
+        for name in &["A", "B", "C", "D", "E", "F"] {
+            graph.nodes.insert(name.to_string(), Node::default());
+        }
+        for (node1, node2, weight) in &[("A", "B", 1.0), ("A", "C", 2.0), ("A", "F", 3.0), ("B", "C", 10.0), ("D", "E", 5.0)] {
+            graph.edges.insert(key(node1, node2), Edge { weight: *weight, ..Default::default() });
+        }
+
+        graph
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("top-k:3".parse::<Pass>(), Ok(Pass::TopK(3)));
+        assert_eq!("isolated".parse::<Pass>(), Ok(Pass::Isolated));
+        assert!("top-k".parse::<Pass>().is_err());
+        assert_eq!("top-k:2.7".parse::<Pass>(), Err("pruning pass top-k value isn't a positive integer".to_string()));
+        assert!("min-component:-1".parse::<Pass>().is_err());
+        assert_eq!("hub-degree:2.5".parse::<Pass>(), Ok(Pass::HubDegree(2.5)));
+        assert!("unknow:2".parse::<Pass>().is_err());
+    }
+
+    #[test]
+    fn passes() {
+        let mut graph = star();
+        assert_eq!(apply(&mut graph, &Pass::HubDegree(80.0)), (1, 3));
+        assert!(!graph.nodes.contains_key("A"));
+
+        let mut graph = star();
+        assert_eq!(apply(&mut graph, &Pass::TopK(1)), (0, 2));
+        assert!(!graph.edges.contains_key(&key("A", "B")));
+
+        let mut graph = star();
+        assert_eq!(apply(&mut graph, &Pass::RelativeWeight(0.5)), (0, 1));
+        assert!(!graph.edges.contains_key(&key("A", "B")));
+
+        let mut graph = star();
+        assert_eq!(apply(&mut graph, &Pass::MinComponent(3)), (2, 1));
+
+        let mut graph = star();
+        assert_eq!(apply(&mut graph, &Pass::SpanningForest), (0, 1));
+        assert!(!graph.edges.contains_key(&key("A", "B")));
+    }
+}
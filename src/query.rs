@@ -0,0 +1,293 @@
+/* project use */
+use crate::graph::{self, Graph};
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* std use */
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub tig: String,
+    pub begin: u64,
+    pub end: u64,
+}
+
+/* contig:begin-end, a contig alone is the whole contig */
+impl std::str::FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tig, range) = match s.rfind(':') {
+            Some(index) => (&s[..index], Some(&s[index + 1..])),
+            None => (s, None),
+        };
+
+        let (begin, end) = match range {
+            None => (0, u64::MAX),
+            Some(range) => {
+                let mut bounds = range.splitn(2, '-').map(|b| b.replace(',', "").parse::<u64>());
+                match (bounds.next(), bounds.next()) {
+                    (Some(Ok(begin)), Some(Ok(end))) if begin < end => (begin, end),
+                    _ => return Err(format!("region {} isn't contig:begin-end", s)),
+                }
+            }
+        };
+
+        Ok(Region { tig: tig.to_string(), begin, end })
+    }
+}
+
+/* Node reach from queried barcode, with the edge which reach it first */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbour {
+    pub id: String,
+    pub depth: usize,
+    pub parent: Option<String>,
+    pub overlap: u64,
+    pub weight: Option<f64>,
+}
+
+/* Molecule or molecule node overlapping a region */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub id: String,
+    pub barcode: String,
+    pub tig: String,
+    pub begin: u64,
+    pub end: u64,
+    pub overlap: u64,
+}
+
+/* Breadth first search from nodes of barcode, node of a molecule graph match on barcode attribute */
+pub fn graph_neighbours(graph: &Graph, barcode: &str, depth: usize) -> Vec<Neighbour> {
+    let mut neighbours: BTreeMap<&String, Vec<(&String, &graph::Edge)>> = BTreeMap::new();
+    for ((node1, node2), edge) in graph.edges.iter() {
+        neighbours.entry(node1).or_default().push((node2, edge));
+        neighbours.entry(node2).or_default().push((node1, edge));
+    }
+
+    let starts = graph.nodes.iter().filter(|(id, node)| *id == barcode || node.barcode.as_deref() == Some(barcode)).map(|(id, _)| id.clone());
+    let mut result: Vec<Neighbour> = starts.map(|id| Neighbour { id, depth: 0, parent: None, overlap: 0, weight: None }).collect();
+    let mut seen: BTreeSet<String> = result.iter().map(|n| n.id.clone()).collect();
+
+    let mut frontier = 0;
+    for level in 1..=depth {
+        let end = result.len();
+        for index in frontier..end {
+            let id = result[index].id.clone();
+            for (other, edge) in neighbours.get(&id).map(|n| n.as_slice()).unwrap_or(&[]) {
+                if seen.insert(other.to_string()) {
+                    result.push(Neighbour {
+                        id: other.to_string(),
+                        depth: level,
+                        parent: Some(id.clone()),
+                        overlap: edge.max_ovl,
+                        weight: Some(edge.weight),
+                    });
+                }
+            }
+        }
+        frontier = end;
+    }
+
+    result
+}
+
+/* Molecules of a contig sorted by begin, with length of longest molecule to bound search of overlapping molecules */
+struct Sorted<'a> {
+    molecules: Vec<(u64, u64, &'a String)>,
+    longest: u64,
+}
+
+/* Barcodes with a molecule overlapping a molecule of a reached barcode on same contig upper than threshold, as edges of build, overlap is the maximal molecule overlap */
+pub fn table_neighbours(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, barcode: &str, depth: usize, threshold: u64) -> Vec<Neighbour> {
+    let mut tig2sorted: BTreeMap<&String, Sorted> = BTreeMap::new();
+    let mut barcode2mols: BTreeMap<&String, Vec<(&String, u64, u64)>> = BTreeMap::new();
+    for (tig, barcode2premol2pos) in tig2barcode2premol2pos {
+        let mut molecules: Vec<(u64, u64, &String)> = barcode2premol2pos.iter().flat_map(|(barcode, mols)| mols.iter().map(move |(begin, end)| (*begin, *end, barcode))).collect();
+        molecules.sort_unstable();
+
+        for (begin, end, barcode) in molecules.iter() {
+            barcode2mols.entry(barcode).or_default().push((tig, *begin, *end));
+        }
+        let longest = molecules.iter().map(|(begin, end, _)| end - begin).max().unwrap_or(0);
+        tig2sorted.insert(tig, Sorted { molecules, longest });
+    }
+
+    let mut result = Vec::new();
+    if barcode2mols.contains_key(&barcode.to_string()) {
+        result.push(Neighbour { id: barcode.to_string(), depth: 0, parent: None, overlap: 0, weight: None });
+    }
+    let mut seen: BTreeSet<String> = result.iter().map(|n| n.id.clone()).collect();
+
+    let mut frontier = 0;
+    for level in 1..=depth {
+        let end = result.len();
+        for index in frontier..end {
+            let id = result[index].id.clone();
+
+            let mut overlaps: BTreeMap<&String, u64> = BTreeMap::new();
+            for (tig, mol_begin, mol_end) in barcode2mols.get(&id).into_iter().flatten() {
+                let sorted = &tig2sorted[tig];
+
+                /* an overlapping molecule begin after begin of molecule minus longest molecule and before its end */
+                let first = sorted.molecules.partition_point(|(begin, _, _)| *begin < mol_begin.saturating_sub(sorted.longest));
+                for (begin, end, other) in sorted.molecules[first..].iter().take_while(|(begin, _, _)| begin < mol_end) {
+                    if *other == &id || seen.contains(*other) {
+                        continue;
+                    }
+
+                    if let Some(overlap) = graph::get_ovl((*mol_begin, *mol_end), (*begin, *end)).filter(|o| *o > threshold) {
+                        let max = overlaps.entry(other).or_default();
+                        *max = (*max).max(overlap);
+                    }
+                }
+            }
+
+            for (other, overlap) in overlaps {
+                seen.insert(other.clone());
+                result.push(Neighbour { id: other.clone(), depth: level, parent: Some(id.clone()), overlap, weight: None });
+            }
+        }
+        frontier = end;
+    }
+
+    result
+}
+
+pub fn table_region(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos, region: &Region) -> Vec<Hit> {
+    let mut hits: Vec<Hit> = tig2barcode2premol2pos
+        .get(&region.tig)
+        .into_iter()
+        .flat_map(|barcode2premol2pos| barcode2premol2pos.iter())
+        .flat_map(|(barcode, mols)| mols.iter().map(move |mol| (barcode, *mol)))
+        .filter_map(|(barcode, mol)| {
+            intersection(mol, region).map(|overlap| Hit {
+                id: barcode.clone(),
+                barcode: barcode.clone(),
+                tig: region.tig.clone(),
+                begin: mol.0,
+                end: mol.1,
+                overlap,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| (a.begin, a.end, &a.barcode).cmp(&(b.begin, b.end, &b.barcode)));
+
+    hits
+}
+
+/* Length of molecule in region, a molecule sharing a bound with region is in region */
+fn intersection(mol: (u64, u64), region: &Region) -> Option<u64> {
+    let begin = mol.0.max(region.begin);
+    let end = mol.1.min(region.end);
+
+    if begin < end {
+        Some(end - begin)
+    } else {
+        None
+    }
+}
+
+/* Only nodes of a molecule graph have a position */
+pub fn graph_region(graph: &Graph, region: &Region) -> Result<Vec<Hit>, String> {
+    if !graph.nodes.is_empty() && graph.nodes.values().all(|n| n.contig.is_none()) {
+        return Err("nodes of graph have no position, region query need a molecule graph or a molecule table".to_string());
+    }
+
+    let mut hits: Vec<Hit> = graph
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.contig.as_deref() == Some(region.tig.as_str()))
+        .filter_map(|(id, node)| {
+            let mol = (node.begin?, node.end?);
+            intersection(mol, region).map(|overlap| Hit {
+                id: id.clone(),
+                barcode: node.barcode.clone().unwrap_or_else(|| id.clone()),
+                tig: region.tig.clone(),
+                begin: mol.0,
+                end: mol.1,
+                overlap,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| (a.begin, a.end, &a.id).cmp(&(b.begin, b.end, &b.id)));
+
+    Ok(hits)
+}
+
+/* Graph induced by nodes */
+pub fn subgraph(graph: &Graph, nodes: &BTreeSet<String>) -> Graph {
+    Graph {
+        model: graph.model,
+        nodes: graph.nodes.iter().filter(|(id, _)| nodes.contains(*id)).map(|(id, node)| (id.clone(), node.clone())).collect(),
+        edges: graph.edges.iter().filter(|((node1, node2), _)| nodes.contains(node1) && nodes.contains(node2)).map(|(key, edge)| (key.clone(), edge.clone())).collect(),
+    }
+}
+
+pub fn write_neighbours<W: Write>(writer: &mut W, neighbours: &[Neighbour]) -> std::io::Result<()> {
+    let na = |value: Option<String>| value.unwrap_or_else(|| "NA".to_string());
+
+    writeln!(writer, "node\tdepth\tparent\toverlap\tweight")?;
+    for neighbour in neighbours {
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}", neighbour.id, neighbour.depth, na(neighbour.parent.clone()), neighbour.overlap, na(neighbour.weight.map(|w| w.to_string())))?;
+    }
+
+    Ok(())
+}
+
+pub fn write_hits<W: Write>(writer: &mut W, hits: &[Hit]) -> std::io::Result<()> {
+    writeln!(writer, "node\tbarcode\tcontig\tbegin\tend\toverlap")?;
+    for hit in hits {
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", hit.id, hit.barcode, hit.tig, hit.begin, hit.end, hit.overlap)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use crate::graph::{key, Edge, Node};
+
+    #[test]
+    fn region() {
+        assert_eq!("tig1:1,000-2000".parse(), Ok(Region { tig: "tig1".to_string(), begin: 1000, end: 2000 }));
+        assert!("tig1:2000-1000".parse::<Region>().is_err());
+        assert_eq!("tig1".parse::<Region>().map(|r| (r.begin, r.end)), Ok((0, u64::MAX)));
+    }
+
+    #[test]
+    fn neighbours() {
+        let mut graph = Graph::default();
+        for name in &["A", "B", "C", "D"] {
+            graph.nodes.insert(name.to_string(), Node::default());
+        }
+        graph.edges.insert(key("A", "B"), Edge { max_ovl: 10, weight: 10.0, ..Default::default() });
+        graph.edges.insert(key("B", "C"), Edge { max_ovl: 20, weight: 20.0, ..Default::default() });
+        graph.edges.insert(key("C", "D"), Edge { max_ovl: 30, weight: 30.0, ..Default::default() });
+
+        let reached = graph_neighbours(&graph, "B", 1);
+        assert_eq!(reached.iter().map(|n| (n.id.as_str(), n.depth)).collect::<Vec<_>>(), vec![("B", 0), ("A", 1), ("C", 1)]);
+        assert_eq!(graph_neighbours(&graph, "A", 3).last().map(|n| (n.id.as_str(), n.parent.as_deref(), n.overlap)), Some(("D", Some("C"), 30)));
+        assert_eq!(subgraph(&graph, &reached.iter().map(|n| n.id.clone()).collect()).edges.len(), 2);
+
+        let mut table = Tig2Barcode2Premol2Pos::new();
+        let barcode2premol2pos = table.entry("tig1".to_string()).or_default();
+        barcode2premol2pos.insert("A".to_string(), vec![(0, 1000)]);
+        barcode2premol2pos.insert("B".to_string(), vec![(800, 2000)]);
+        barcode2premol2pos.insert("C".to_string(), vec![(1500, 3000)]);
+
+        let reached = table_neighbours(&table, "A", 2, 0);
+        assert_eq!(reached.iter().map(|n| (n.id.as_str(), n.depth, n.overlap)).collect::<Vec<_>>(), vec![("A", 0, 0), ("B", 1, 200), ("C", 2, 500)]);
+        assert_eq!(table_neighbours(&table, "A", 2, 300).len(), 1);
+        assert_eq!(table_neighbours(&table, "C", 1, 300).iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), vec!["C", "B"]);
+
+        let hits = table_region(&table, &"tig1:900-1600".parse().unwrap());
+        assert_eq!(hits.iter().map(|h| (h.barcode.as_str(), h.overlap)).collect::<Vec<_>>(), vec![("A", 100), ("B", 700), ("C", 100)]);
+        assert!(graph_region(&graph, &"tig1:900-1600".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+/* project use */
+use crate::ends::{End, Link, Profiles, Side};
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* std use */
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Greedy,
+    SpanningTree,
+}
+
+impl Method {
+    pub const NAMES: &'static [&'static str] = &["greedy", "spanning-tree"];
+}
+
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(Method::Greedy),
+            "spanning-tree" => Ok(Method::SpanningTree),
+            _ => Err(format!("unknow scaffolding method {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub link: Link,
+    pub gap: u64,
+}
+
+/* Contigs with their orientation, gap after each contig is store with it, last gap is always 0 */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scaffold {
+    pub name: String,
+    pub parts: Vec<(String, char, u64)>,
+}
+
+/* Median length of molecules */
+pub fn molecule_length(tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos) -> u64 {
+    let mut lengths: Vec<u64> = tig2barcode2premol2pos.values().flat_map(|b| b.values()).flat_map(|p| p.iter().map(|(begin, end)| end - begin)).collect();
+    if lengths.is_empty() {
+        return 0;
+    }
+    lengths.sort_unstable();
+
+    lengths[lengths.len() / 2]
+}
+
+/* A molecule shared by two ends cover the end of first contig, the gap and the begin of second contig, gap is the median over shared barcodes of molecule length minus extent in each contig */
+pub fn gap(profiles: &Profiles, link: &Link, molecule_length: u64, min_gap: u64) -> u64 {
+    let barcodes1 = &profiles[&link.end1];
+    let barcodes2 = &profiles[&link.end2];
+
+    let mut gaps: Vec<i64> = barcodes1
+        .iter()
+        .filter_map(|(barcode, extent1)| barcodes2.get(barcode).map(|extent2| molecule_length as i64 - *extent1 as i64 - *extent2 as i64))
+        .collect();
+    if gaps.is_empty() {
+        return min_gap;
+    }
+    gaps.sort_unstable();
+
+    (gaps[gaps.len() / 2].max(0) as u64).max(min_gap)
+}
+
+/* Choose links where each contig end is used at most once and without cycle */
+pub fn select(links: &[Link], method: Method) -> Vec<Link> {
+    let mut sorted: Vec<&Link> = links.iter().collect();
+    sorted.sort_by(|a, b| {
+        b.shared
+            .cmp(&a.shared)
+            .then(b.jaccard.partial_cmp(&a.jaccard).unwrap_or(std::cmp::Ordering::Equal))
+            .then(a.end1.cmp(&b.end1))
+            .then(a.end2.cmp(&b.end2))
+    });
+
+    let mut index: HashMap<&String, usize> = HashMap::new();
+    for link in sorted.iter() {
+        for tig in &[&link.end1.0, &link.end2.0] {
+            let len = index.len();
+            index.entry(tig).or_insert(len);
+        }
+    }
+    let mut parent: Vec<usize> = (0..index.len()).collect();
+
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut used: BTreeMap<&End, usize> = BTreeMap::new();
+    let mut selected = Vec::new();
+    for link in sorted {
+        // greedy never reuse an end, spanning tree keep all tree links and remove ambiguous ends after
+        if method == Method::Greedy && (used.contains_key(&link.end1) || used.contains_key(&link.end2)) {
+            continue;
+        }
+
+        let root1 = find(&mut parent, index[&link.end1.0]);
+        let root2 = find(&mut parent, index[&link.end2.0]);
+        if root1 == root2 {
+            continue;
+        }
+
+        parent[root1] = root2;
+        *used.entry(&link.end1).or_insert(0) += 1;
+        *used.entry(&link.end2).or_insert(0) += 1;
+        selected.push(link);
+    }
+
+    selected.into_iter().filter(|l| used[&l.end1] == 1 && used[&l.end2] == 1).cloned().collect()
+}
+
+/* Follow joins from each contig with a free end, contigs without join are alone in their scaffold */
+pub fn scaffolds(tig2len: &BTreeMap<String, u64>, joins: &[Join]) -> Vec<Scaffold> {
+    let mut end2join: HashMap<&End, (&End, u64)> = HashMap::new();
+    for join in joins {
+        end2join.insert(&join.link.end1, (&join.link.end2, join.gap));
+        end2join.insert(&join.link.end2, (&join.link.end1, join.gap));
+    }
+
+    let mut visited: HashMap<&String, bool> = tig2len.keys().map(|t| (t, false)).collect();
+    let mut scaffolds = Vec::new();
+    for tig in tig2len.keys() {
+        if visited[tig] {
+            continue;
+        }
+
+        let start = (tig.clone(), Side::Start);
+        let end = (tig.clone(), Side::End);
+        let mut current = match (end2join.contains_key(&start), end2join.contains_key(&end)) {
+            (false, _) => start,
+            (true, false) => end,
+            (true, true) => continue, // inside a scaffold, reach from one of its extremity
+        };
+
+        let mut parts = Vec::new();
+        loop {
+            visited.insert(tig2len.get_key_value(&current.0).expect("contig without length").0, true);
+
+            let (orientation, exit) = match current.1 {
+                Side::Start => ('+', (current.0.clone(), Side::End)),
+                Side::End => ('-', (current.0.clone(), Side::Start)),
+            };
+
+            match end2join.get(&exit) {
+                Some((next, gap)) => {
+                    parts.push((current.0.clone(), orientation, *gap));
+                    current = (*next).clone();
+                }
+                None => {
+                    parts.push((current.0.clone(), orientation, 0));
+                    break;
+                }
+            }
+        }
+
+        scaffolds.push(Scaffold {
+            name: format!("scaffold{}", scaffolds.len() + 1),
+            parts,
+        });
+    }
+
+    scaffolds
+}
+
+pub fn write_agp<W: Write>(writer: &mut W, tig2len: &BTreeMap<String, u64>, scaffolds: &[Scaffold]) -> std::io::Result<()> {
+    writeln!(writer, "##agp-version\t2.1")?;
+
+    for scaffold in scaffolds {
+        let mut position = 1;
+        let mut part = 1;
+
+        for (tig, orientation, gap) in scaffold.parts.iter() {
+            let len = tig2len[tig];
+            writeln!(writer, "{}\t{}\t{}\t{}\tW\t{}\t1\t{}\t{}", scaffold.name, position, position + len - 1, part, tig, len, orientation)?;
+            position += len;
+            part += 1;
+
+            if *gap > 0 {
+                writeln!(writer, "{}\t{}\t{}\t{}\tN\t{}\tscaffold\tyes\tunspecified", scaffold.name, position, position + gap - 1, part, gap)?;
+                position += gap;
+                part += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/* Scaffolds are write in gfa 1.2, joins as J line with gap length and scaffolds as P line */
+pub fn write_gfa<W: Write>(writer: &mut W, tig2len: &BTreeMap<String, u64>, scaffolds: &[Scaffold]) -> std::io::Result<()> {
+    writeln!(writer, "H\tVN:Z:1.2")?;
+
+    for (tig, len) in tig2len.iter() {
+        writeln!(writer, "S\t{}\t*\tLN:i:{}", tig, len)?;
+    }
+
+    for scaffold in scaffolds {
+        for pair in scaffold.parts.windows(2) {
+            writeln!(writer, "J\t{}\t{}\t{}\t{}\t{}", pair[0].0, pair[0].1, pair[1].0, pair[1].1, pair[0].2)?;
+        }
+    }
+
+    for scaffold in scaffolds {
+        let path: Vec<String> = scaffold.parts.iter().map(|(tig, orientation, _)| format!("{}{}", tig, orientation)).collect();
+        writeln!(writer, "P\t{}\t{}\t*", scaffold.name, path.join(";"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn link(tig1: &str, side1: Side, tig2: &str, side2: Side, shared: usize) -> Link {
+        Link {
+            end1: (tig1.to_string(), side1),
+            end2: (tig2.to_string(), side2),
+            shared,
+            jaccard: 0.5,
+        }
+    }
+
+    #[test]
+    fn select_links() {
+        let links = vec![
+            link("tig1", Side::End, "tig2", Side::Start, 10),
+            link("tig1", Side::End, "tig3", Side::Start, 5),
+            link("tig3", Side::End, "tig4", Side::Start, 4),
+            link("tig2", Side::End, "tig4", Side::End, 3),
+        ];
+
+        let greedy = select(&links, Method::Greedy);
+        assert_eq!(greedy, vec![links[0].clone(), links[2].clone(), links[3].clone()]);
+
+        // tig1 end is ambiguous in spanning tree, only link between tig3 and tig4 is kept
+        let tree = select(&links, Method::SpanningTree);
+        assert_eq!(tree, vec![links[2].clone()]);
+    }
+
+    #[test]
+    fn scaffold_path() {
+        let mut tig2len = BTreeMap::new();
+        for (tig, len) in &[("tig1", 1000), ("tig2", 2000), ("tig3", 3000), ("tig4", 500)] {
+            tig2len.insert(tig.to_string(), *len);
+        }
+
+        let joins = vec![
+            Join { link: link("tig1", Side::End, "tig2", Side::Start, 10), gap: 100 },
+            Join { link: link("tig2", Side::End, "tig3", Side::End, 4), gap: 200 },
+        ];
+
+        let scaffolds = scaffolds(&tig2len, &joins);
+        assert_eq!(scaffolds.len(), 2);
+        assert_eq!(scaffolds[0].parts, vec![("tig1".to_string(), '+', 100), ("tig2".to_string(), '+', 200), ("tig3".to_string(), '-', 0)]);
+        assert_eq!(scaffolds[1].parts, vec![("tig4".to_string(), '+', 0)]);
+
+        let mut agp = Vec::new();
+        write_agp(&mut agp, &tig2len, &scaffolds).unwrap();
+        let agp = String::from_utf8(agp).unwrap();
+        assert!(agp.contains("scaffold1\t1001\t1100\t2\tN\t100\tscaffold\tyes\tunspecified\n"));
+        assert!(agp.contains("scaffold1\t3301\t6300\t5\tW\ttig3\t1\t3000\t-\n"));
+    }
+}
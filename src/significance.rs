@@ -0,0 +1,117 @@
+/* project use */
+use crate::graph::Graph;
+
+/* Under random placement two molecules of length len1 and len2 on a contig of length tig_len overlap by more than threshold with probability (len1 + len2 - 2 * threshold) / tig_len */
+pub fn expected_pairs(poss1: &[(u64, u64)], poss2: &[(u64, u64)], tig_len: u64, threshold: u64) -> f64 {
+    if tig_len == 0 {
+        return 0.0;
+    }
+
+    let mut expected = 0.0;
+    for (begin1, end1) in poss1 {
+        for (begin2, end2) in poss2 {
+            let span = ((end1 - begin1) + (end2 - begin2)).saturating_sub(2 * threshold);
+            expected += (span as f64 / tig_len as f64).min(1.0);
+        }
+    }
+
+    expected
+}
+
+pub fn expected_overlap(covered1: u64, covered2: u64, tig_len: u64) -> f64 {
+    if tig_len == 0 {
+        return 0.0;
+    }
+
+    covered1 as f64 * covered2 as f64 / tig_len as f64
+}
+
+/* Probability to observe at least k events for a Poisson of mean lambda */
+pub fn poisson_sf(k: u64, lambda: f64) -> f64 {
+    if k == 0 {
+        return 1.0;
+    }
+    if lambda <= 0.0 {
+        return 0.0;
+    }
+
+    let ln_fact: f64 = (1..=k).map(|i| (i as f64).ln()).sum();
+    let first = (-lambda + k as f64 * lambda.ln() - ln_fact).exp();
+
+    if lambda > k as f64 {
+        // tail is large, sum terms below k and take complement
+        let mut cdf = 0.0;
+        let mut term = first;
+        for i in (1..=k).rev() {
+            term *= i as f64 / lambda;
+            cdf += term;
+        }
+
+        return (1.0 - cdf).max(0.0);
+    }
+
+    let mut sf = 0.0;
+    let mut term = first;
+    let mut i = k;
+    while term > sf * 1e-16 && term > 0.0 {
+        sf += term;
+        i += 1;
+        term *= lambda / i as f64;
+    }
+
+    sf.min(1.0)
+}
+
+/* Benjamini-Hochberg adjusted p-value, in the same order as input */
+pub fn adjust(pvalues: &[f64]) -> Vec<f64> {
+    let nb = pvalues.len();
+    let mut order: Vec<usize> = (0..nb).collect();
+    order.sort_by(|a, b| pvalues[*b].partial_cmp(&pvalues[*a]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut adjusted = vec![0.0; nb];
+    let mut min = 1.0_f64;
+    for (rank, index) in order.into_iter().enumerate() {
+        let value = pvalues[index] * nb as f64 / (nb - rank) as f64;
+        min = min.min(value);
+        adjusted[index] = min;
+    }
+
+    adjusted
+}
+
+pub fn filter_fdr(graph: &mut Graph, fdr: f64) -> usize {
+    let pvalues: Vec<f64> = graph.edges.values().map(|e| e.pvalue).collect();
+    let adjusted = adjust(&pvalues);
+
+    let before = graph.edges.len();
+    let mut keep = adjusted.into_iter().map(|q| q <= fdr);
+    graph.edges.retain(|_, _| keep.next().unwrap_or(false));
+    graph.remove_isolated();
+
+    before - graph.edges.len()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn poisson() {
+        assert_eq!(poisson_sf(0, 2.0), 1.0);
+        assert_eq!(poisson_sf(3, 0.0), 0.0);
+        assert!((poisson_sf(1, 2.0) - (1.0 - (-2.0_f64).exp())).abs() < 1e-12);
+        assert!((poisson_sf(2, 0.5) - (1.0 - 1.5 * (-0.5_f64).exp())).abs() < 1e-12);
+        assert!(poisson_sf(30, 0.1) < 1e-50);
+    }
+
+    #[test]
+    fn benjamini_hochberg() {
+        let adjusted = adjust(&[0.01, 0.04, 0.03, 0.5]);
+
+        assert!((adjusted[0] - 0.04).abs() < 1e-12);
+        assert!((adjusted[1] - 0.0533333333).abs() < 1e-9);
+        assert!((adjusted[2] - 0.0533333333).abs() < 1e-9);
+        assert!((adjusted[3] - 0.5).abs() < 1e-12);
+    }
+}
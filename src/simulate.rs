@@ -0,0 +1,259 @@
+/* project use */
+use crate::file;
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+
+/* std use */
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Tsv,
+    Sam,
+}
+
+impl Format {
+    pub const NAMES: &'static [&'static str] = &["tsv", "sam"];
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tsv" => Ok(Format::Tsv),
+            "sam" => Ok(Format::Sam),
+            _ => Err(format!("unknow simulation format {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub barcodes: usize,
+    pub molecules_per_barcode: f64,
+    pub molecule_length: f64,
+    pub reads_per_molecule: f64,
+    pub read_length: u64,
+    pub seed: u64,
+}
+
+/* Molecule truth, begin and end are 0-based half-open, reads are 1-based mapping positions */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Molecule {
+    pub id: usize,
+    pub barcode: String,
+    pub tig: String,
+    pub begin: u64,
+    pub end: u64,
+    pub reads: Vec<u64>,
+}
+
+/* splitmix64, small and stable across versions so a seed always give the same dataset */
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /* uniform in [0, 1) */
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /* uniform in [0, n) */
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+
+    pub fn exponential(&mut self, mean: f64) -> f64 {
+        -mean * (1.0 - self.uniform()).ln()
+    }
+
+    pub fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /* Knuth algorithm for small mean, normal approximation otherwise */
+    pub fn poisson(&mut self, mean: f64) -> u64 {
+        if mean > 30.0 {
+            return (mean + mean.sqrt() * self.gaussian()).round().max(0.0) as u64;
+        }
+
+        let limit = (-mean).exp();
+        let mut k = 0;
+        let mut p = self.uniform();
+        while p > limit {
+            k += 1;
+            p *= self.uniform();
+        }
+
+        k
+    }
+}
+
+/* Molecules are place on contigs with probability proportional to contig length, length follow an exponential distribution */
+pub fn simulate(tig2len: &BTreeMap<String, u64>, config: &Config) -> Vec<Molecule> {
+    let mut rng = Rng::new(config.seed);
+
+    let tigs: Vec<(&String, u64)> = tig2len.iter().filter(|(_, l)| **l > config.read_length).map(|(t, l)| (t, *l)).collect();
+    let total: u64 = tigs.iter().map(|(_, l)| l).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut barcodes = BTreeSet::new();
+    while barcodes.len() < config.barcodes {
+        let barcode: String = (0..16).map(|_| ['A', 'C', 'G', 'T'][rng.below(4) as usize]).collect();
+        barcodes.insert(barcode);
+    }
+
+    let mut molecules = Vec::new();
+    for barcode in barcodes {
+        for _ in 0..rng.poisson(config.molecules_per_barcode) {
+            let mut target = rng.below(total);
+            let (tig, tig_len) = tigs
+                .iter()
+                .find(|(_, l)| {
+                    if target < *l {
+                        true
+                    } else {
+                        target -= l;
+                        false
+                    }
+                })
+                .unwrap();
+
+            let length = (rng.exponential(config.molecule_length) as u64).clamp(config.read_length, *tig_len);
+            let begin = rng.below(tig_len - length + 1);
+            let end = begin + length;
+
+            let mut reads: Vec<u64> = (0..rng.poisson(config.reads_per_molecule)).map(|_| begin + rng.below(length - config.read_length + 1) + 1).collect();
+            reads.sort_unstable();
+
+            molecules.push(Molecule {
+                id: molecules.len(),
+                barcode: barcode.clone(),
+                tig: tig.to_string(),
+                begin,
+                end,
+                reads,
+            });
+        }
+    }
+
+    molecules
+}
+
+/* Reads in ema summary format with @SQ lines, so contig length source isn't required */
+pub fn write_tsv<W: Write>(writer: &mut W, tig2len: &BTreeMap<String, u64>, molecules: &[Molecule]) -> std::io::Result<()> {
+    for (tig, len) in tig2len.iter() {
+        writeln!(writer, "@SQ\tSN:{}\tLN:{}", tig, len)?;
+    }
+
+    let mut read_id = 0;
+    for molecule in molecules {
+        for pos in molecule.reads.iter() {
+            writeln!(writer, "read{}\t{}\t{}\t{}-1\t{}", read_id, molecule.tig, pos, molecule.barcode, molecule.id)?;
+            read_id += 1;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_sam<W: Write>(writer: &mut W, tig2len: &BTreeMap<String, u64>, molecules: &[Molecule], read_length: u64) -> std::io::Result<()> {
+    writeln!(writer, "@HD\tVN:1.6\tSO:unsorted")?;
+    for (tig, len) in tig2len.iter() {
+        writeln!(writer, "@SQ\tSN:{}\tLN:{}", tig, len)?;
+    }
+    writeln!(writer, "@PG\tID:{}\tPN:{}\tVN:{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+
+    let mut read_id = 0;
+    for molecule in molecules {
+        for pos in molecule.reads.iter() {
+            writeln!(writer, "read{}\t0\t{}\t{}\t60\t{}M\t*\t0\t0\t*\t*\tBX:Z:{}-1\tMI:i:{}", read_id, molecule.tig, pos, read_length, molecule.barcode, molecule.id)?;
+            read_id += 1;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_truth<W: Write>(writer: &mut W, molecules: &[Molecule]) -> std::io::Result<()> {
+    writeln!(writer, "molecule\tbarcode\tcontig\tbegin\tend\treads")?;
+    for molecule in molecules {
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", molecule.id, molecule.barcode, molecule.tig, molecule.begin, molecule.end, molecule.reads.len())?;
+    }
+
+    Ok(())
+}
+
+/* Truth molecules without reads, grouped like inferred molecules by contig and barcode */
+pub fn read_truth(path: &str) -> Tig2Barcode2Premol2Pos {
+    let mut tig2barcode2mol = Tig2Barcode2Premol2Pos::new();
+
+    let (reader, _) = file::get_readable_file(path);
+    for line in BufReader::new(reader).lines().skip(1) {
+        let line = line.expect("Error during truth parsing");
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 5 {
+            continue;
+        }
+
+        let begin = fields[3].parse::<u64>().expect("truth begin isn't a number");
+        let end = fields[4].parse::<u64>().expect("truth end isn't a number");
+        tig2barcode2mol.entry(fields[2].to_string()).or_default().entry(fields[1].to_string()).or_default().push((begin, end));
+    }
+
+    tig2barcode2mol
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn simulation() {
+        let mut tig2len = BTreeMap::new();
+        tig2len.insert("tig1".to_string(), 200000);
+        tig2len.insert("tig2".to_string(), 100);
+
+        let config = Config {
+            barcodes: 20,
+            molecules_per_barcode: 5.0,
+            molecule_length: 30000.0,
+            reads_per_molecule: 40.0,
+            read_length: 150,
+            seed: 42,
+        };
+
+        let molecules = simulate(&tig2len, &config);
+        assert!(!molecules.is_empty());
+        assert_eq!(molecules, simulate(&tig2len, &config));
+        assert!(molecules.iter().all(|m| m.tig == "tig1" && m.end <= 200000));
+        assert!(molecules.iter().all(|m| m.reads.iter().all(|r| *r > m.begin && *r + 149 <= m.end)));
+
+        let mut output = Vec::new();
+        write_tsv(&mut output, &tig2len, &molecules[..1]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("@SQ\tSN:tig1\tLN:200000\n@SQ\tSN:tig2\tLN:100\nread0\ttig1\t"));
+    }
+}
@@ -0,0 +1,221 @@
+/* project use */
+use crate::graph::{self, Graph};
+
+/* crates use */
+use serde::Serialize;
+
+/* std use */
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write;
+
+#[derive(Debug, Serialize)]
+pub struct Bin {
+    pub begin: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Statistics {
+    pub weight_model: String,
+    pub nodes: usize,
+    pub edges: usize,
+    pub components: usize,
+    pub largest_component: usize,
+    pub average_clustering: f64,
+    pub transitivity: f64,
+    pub degeneracy: usize,
+    pub degree_distribution: BTreeMap<usize, usize>,
+    pub component_sizes: BTreeMap<usize, usize>,
+    pub core_distribution: BTreeMap<usize, usize>,
+    pub weight_histogram: Vec<Bin>,
+}
+
+pub fn compute(graph: &Graph, bins: usize) -> Statistics {
+    let (_, neighbours) = graph.adjacency();
+
+    let mut degree_distribution = BTreeMap::new();
+    for around in neighbours.iter() {
+        *degree_distribution.entry(around.len()).or_insert(0) += 1;
+    }
+
+    let component = graph::components(&neighbours);
+    let mut size = BTreeMap::new();
+    for c in component.iter() {
+        *size.entry(*c).or_insert(0) += 1;
+    }
+    let mut component_sizes = BTreeMap::new();
+    for s in size.values() {
+        *component_sizes.entry(*s).or_insert(0) += 1;
+    }
+
+    let (average_clustering, transitivity) = clustering(&neighbours);
+
+    let mut core_distribution = BTreeMap::new();
+    for core in core_number(&neighbours) {
+        *core_distribution.entry(core).or_insert(0) += 1;
+    }
+
+    Statistics {
+        weight_model: graph.model.to_string(),
+        nodes: graph.nodes.len(),
+        edges: graph.edges.len(),
+        components: size.len(),
+        largest_component: size.values().max().cloned().unwrap_or(0),
+        average_clustering,
+        transitivity,
+        degeneracy: core_distribution.keys().max().cloned().unwrap_or(0),
+        degree_distribution,
+        component_sizes,
+        core_distribution,
+        weight_histogram: histogram(graph.edges.values().map(|e| e.weight), bins),
+    }
+}
+
+/* Average of local clustering coefficient and global transitivity */
+pub fn clustering(neighbours: &[Vec<(usize, f64)>]) -> (f64, f64) {
+    let sets: Vec<HashSet<usize>> = neighbours.iter().map(|n| n.iter().map(|(j, _)| *j).collect()).collect();
+
+    let mut sum_local = 0.0;
+    let mut triangles = 0;
+    let mut triples = 0;
+    for (i, around) in sets.iter().enumerate() {
+        let degree = around.len();
+        if degree < 2 {
+            continue;
+        }
+
+        let mut links = 0;
+        for j in around.iter() {
+            links += sets[*j].iter().filter(|k| **k != i && around.contains(k)).count();
+        }
+        links /= 2;
+
+        let possible = degree * (degree - 1) / 2;
+        sum_local += links as f64 / possible as f64;
+        triangles += links;
+        triples += possible;
+    }
+
+    let average = if sets.is_empty() { 0.0 } else { sum_local / sets.len() as f64 };
+    let transitivity = if triples == 0 { 0.0 } else { triangles as f64 / triples as f64 };
+
+    (average, transitivity)
+}
+
+/* k-core decomposition by iterative removal of minimal degree node */
+pub fn core_number(neighbours: &[Vec<(usize, f64)>]) -> Vec<usize> {
+    let mut degree: Vec<usize> = neighbours.iter().map(|n| n.len()).collect();
+    let mut queue: BTreeSet<(usize, usize)> = degree.iter().enumerate().map(|(i, d)| (*d, i)).collect();
+    let mut removed = vec![false; neighbours.len()];
+    let mut core = vec![0; neighbours.len()];
+
+    let mut current = 0;
+    while let Some((d, node)) = queue.iter().next().cloned() {
+        queue.remove(&(d, node));
+        removed[node] = true;
+
+        current = current.max(d);
+        core[node] = current;
+
+        for (next, _) in neighbours[node].iter() {
+            if !removed[*next] {
+                queue.remove(&(degree[*next], *next));
+                degree[*next] -= 1;
+                queue.insert((degree[*next], *next));
+            }
+        }
+    }
+
+    core
+}
+
+pub fn histogram<I: Iterator<Item = f64>>(values: I, bins: usize) -> Vec<Bin> {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() || bins == 0 {
+        return Vec::new();
+    }
+
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let width = if max > min { (max - min) / bins as f64 } else { 1.0 };
+
+    let mut histogram: Vec<Bin> = (0..bins)
+        .map(|i| Bin {
+            begin: min + i as f64 * width,
+            end: min + (i + 1) as f64 * width,
+            count: 0,
+        })
+        .collect();
+
+    for value in values {
+        let index = (((value - min) / width) as usize).min(bins - 1);
+        histogram[index].count += 1;
+    }
+
+    histogram
+}
+
+pub fn write_tsv<W: Write>(writer: &mut W, stats: &Statistics) -> std::io::Result<()> {
+    writeln!(writer, "section\tkey\tvalue")?;
+    writeln!(writer, "summary\tweight_model\t{}", stats.weight_model)?;
+    writeln!(writer, "summary\tnodes\t{}", stats.nodes)?;
+    writeln!(writer, "summary\tedges\t{}", stats.edges)?;
+    writeln!(writer, "summary\tcomponents\t{}", stats.components)?;
+    writeln!(writer, "summary\tlargest_component\t{}", stats.largest_component)?;
+    writeln!(writer, "summary\taverage_clustering\t{}", stats.average_clustering)?;
+    writeln!(writer, "summary\ttransitivity\t{}", stats.transitivity)?;
+    writeln!(writer, "summary\tdegeneracy\t{}", stats.degeneracy)?;
+
+    for (degree, count) in stats.degree_distribution.iter() {
+        writeln!(writer, "degree\t{}\t{}", degree, count)?;
+    }
+    for (size, count) in stats.component_sizes.iter() {
+        writeln!(writer, "component_size\t{}\t{}", size, count)?;
+    }
+    for (core, count) in stats.core_distribution.iter() {
+        writeln!(writer, "core\t{}\t{}", core, count)?;
+    }
+    for bin in stats.weight_histogram.iter() {
+        writeln!(writer, "weight\t{}-{}\t{}", bin.begin, bin.end, bin.count)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    /* a triangle with a tail */
+    fn triangle_tail() -> Vec<Vec<(usize, f64)>> {
+        vec![
+            vec![(1, 1.0), (2, 1.0)],
+            vec![(0, 1.0), (2, 1.0)],
+            vec![(0, 1.0), (1, 1.0), (3, 1.0)],
+            vec![(2, 1.0)],
+        ]
+    }
+
+    #[test]
+    fn clustering_coefficient() {
+        let (average, transitivity) = clustering(&triangle_tail());
+
+        assert!((average - (1.0 + 1.0 + 1.0 / 3.0) / 4.0).abs() < 1e-12);
+        assert!((transitivity - 3.0 / 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn k_core() {
+        assert_eq!(core_number(&triangle_tail()), vec![2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn weight_histogram() {
+        let histogram = histogram(vec![0.0, 1.0, 2.0, 10.0].into_iter(), 2);
+
+        assert_eq!(histogram.iter().map(|b| b.count).collect::<Vec<usize>>(), vec![3, 1]);
+        assert_eq!(histogram[1].end, 10.0);
+    }
+}
@@ -0,0 +1,325 @@
+/* project use */
+use crate::file;
+use crate::parse_info::Tig2Barcode2Premol2Pos;
+use crate::query::Region;
+
+/* std use */
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+
+/* Byte range of a contig in table and, for each bin of contig, offset of first molecule overlapping it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: u64,
+    pub end: u64,
+    pub bins: Vec<Option<u64>>,
+}
+
+/* Size and modification time of indexed table, an index is valid only for the same table */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    pub bin: u64,
+    pub size: u64,
+    pub mtime: u128,
+    pub contigs: BTreeMap<String, Block>,
+}
+
+fn stamp(path: &str) -> std::io::Result<(u64, u128)> {
+    let metadata = std::fs::metadata(path)?;
+    let mtime = metadata.modified()?.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+
+    Ok((metadata.len(), mtime))
+}
+
+/* One molecule by line, sorted by contig, begin, end and barcode */
+pub fn write<W: Write>(writer: &mut W, tig2barcode2premol2pos: &Tig2Barcode2Premol2Pos) -> std::io::Result<()> {
+    let mut molecules: Vec<(&String, u64, u64, &String)> = tig2barcode2premol2pos
+        .iter()
+        .flat_map(|(tig, barcode2premol)| barcode2premol.iter().flat_map(move |(barcode, premols)| premols.iter().map(move |(begin, end)| (tig, *begin, *end, barcode))))
+        .collect();
+    molecules.sort_unstable();
+
+    writeln!(writer, "#contig\tbegin\tend\tbarcode")?;
+    for (tig, begin, end, barcode) in molecules {
+        writeln!(writer, "{}\t{}\t{}\t{}", tig, begin, end, barcode)?;
+    }
+
+    Ok(())
+}
+
+/* Contig, begin, end and barcode of a molecule line, None for header or incomplete line */
+fn parse(line: &str) -> Option<(&str, u64, u64, &str)> {
+    if line.starts_with('#') {
+        return None;
+    }
+
+    let fields: Vec<&str> = line.trim_end_matches(&['\n', '\r'][..]).split('\t').collect();
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let begin = fields[1].parse::<u64>().expect("molecule begin isn't a number");
+    let end = fields[2].parse::<u64>().expect("molecule end isn't a number");
+
+    Some((fields[0], begin, end, fields[3]))
+}
+
+pub fn read(path: &str) -> Tig2Barcode2Premol2Pos {
+    let mut tig2barcode2premol2pos = Tig2Barcode2Premol2Pos::new();
+
+    let (reader, _) = file::get_readable_file(path);
+    for line in BufReader::new(reader).lines() {
+        let line = line.expect("Error during molecule table parsing");
+
+        if let Some((tig, begin, end, barcode)) = parse(&line) {
+            tig2barcode2premol2pos.entry(tig.to_string()).or_default().entry(barcode.to_string()).or_default().push((begin, end));
+        }
+    }
+
+    tig2barcode2premol2pos
+}
+
+pub fn index_path(path: &str) -> String {
+    format!("{}.idx", path)
+}
+
+/* Table must be uncompressed, molecules grouped by contig and sorted by begin */
+pub fn index(path: &str, bin: u64) -> Result<Index, String> {
+    let (size, mtime) = stamp(path).map_err(|e| format!("can't open molecule table {}: {}", path, e))?;
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("can't open molecule table {}: {}", path, e))?);
+
+    let mut contigs: BTreeMap<String, Block> = BTreeMap::new();
+    let mut current: Option<(String, u64)> = None;
+    let mut offset = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(|e| format!("can't read molecule table {}: {}", path, e))? as u64;
+        if read == 0 {
+            break;
+        }
+        let line_offset = offset;
+        offset += read;
+
+        let (tig, begin, end, _) = match parse(&line) {
+            Some(molecule) => molecule,
+            None => continue,
+        };
+
+        match current.as_mut() {
+            Some((name, previous)) if name == tig => {
+                if begin < *previous {
+                    return Err(format!("molecules of contig {} aren't sorted by begin", tig));
+                }
+                *previous = begin;
+            }
+            _ => {
+                if contigs.contains_key(tig) {
+                    return Err(format!("molecules of contig {} aren't grouped", tig));
+                }
+                contigs.insert(tig.to_string(), Block { start: line_offset, end: offset, bins: Vec::new() });
+                current = Some((tig.to_string(), begin));
+            }
+        }
+
+        let block = contigs.get_mut(tig).unwrap();
+        block.end = offset;
+        let last = (end.saturating_sub(1) / bin) as usize;
+        if block.bins.len() <= last {
+            block.bins.resize(last + 1, None);
+        }
+        for offset in block.bins[(begin / bin) as usize..=last].iter_mut().filter(|o| o.is_none()) {
+            *offset = Some(line_offset);
+        }
+    }
+
+    Ok(Index { bin, size, mtime, contigs })
+}
+
+pub fn write_index<W: Write>(writer: &mut W, index: &Index) -> std::io::Result<()> {
+    writeln!(writer, "#bin\t{}\tsize\t{}\tmtime\t{}", index.bin, index.size, index.mtime)?;
+    for (tig, block) in index.contigs.iter() {
+        let bins: Vec<String> = block.bins.iter().map(|o| o.map(|o| o.to_string()).unwrap_or_else(|| ".".to_string())).collect();
+        writeln!(writer, "{}\t{}\t{}\t{}", tig, block.start, block.end, bins.join(","))?;
+    }
+
+    Ok(())
+}
+
+pub fn read_index(path: &str) -> Result<Index, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("can't read molecule index {}: {}", path, e))?;
+    let invalid = || format!("molecule index {} isn't valid", path);
+
+    let mut lines = content.lines();
+    let header: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+    let (bin, size, mtime) = match header.as_slice() {
+        ["#bin", bin, "size", size, "mtime", mtime] => (bin.parse().map_err(|_| invalid())?, size.parse().map_err(|_| invalid())?, mtime.parse().map_err(|_| invalid())?),
+        _ => return Err(invalid()),
+    };
+
+    let mut contigs = BTreeMap::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 4 {
+            return Err(invalid());
+        }
+
+        let bins = fields[3].split(',').filter(|b| !b.is_empty()).map(|b| if b == "." { Ok(None) } else { b.parse().map(Some) }).collect::<Result<Vec<Option<u64>>, _>>().map_err(|_| invalid())?;
+        let start = fields[1].parse().map_err(|_| invalid())?;
+        let end = fields[2].parse().map_err(|_| invalid())?;
+        contigs.insert(fields[0].to_string(), Block { start, end, bins });
+    }
+
+    Ok(Index { bin, size, mtime, contigs })
+}
+
+/* Index of table if it exists and table wasn't modified after indexing */
+pub fn valid_index(path: &str) -> Option<Index> {
+    let index_path = index_path(path);
+    if !std::path::Path::new(&index_path).exists() {
+        return None;
+    }
+
+    match (read_index(&index_path), stamp(path)) {
+        (Ok(index), Ok(stamp)) if (index.size, index.mtime) == stamp => Some(index),
+        (Err(message), _) => {
+            log::warn!("{}, table is read without index", message);
+            None
+        }
+        _ => {
+            log::warn!("molecule table {} was modified after indexing, table is read without index", path);
+            None
+        }
+    }
+}
+
+/* A rewritten table invalidate its index */
+pub fn remove_index(path: &str) {
+    let index_path = index_path(path);
+    if std::path::Path::new(&index_path).exists() {
+        std::fs::remove_file(&index_path).unwrap_or_else(|e| panic!("Can't remove molecule index {}: {}", index_path, e));
+    }
+}
+
+/* Read molecules between offsets, stop at first molecule which begin after limit */
+fn read_block(reader: &mut BufReader<File>, tig2barcode2premol2pos: &mut Tig2Barcode2Premol2Pos, start: u64, end: u64, limit: u64) -> std::io::Result<()> {
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut offset = start;
+    let mut line = String::new();
+    while offset < end {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+            break;
+        }
+        offset += read as u64;
+
+        if let Some((tig, begin, end, barcode)) = parse(&line) {
+            if begin >= limit {
+                break;
+            }
+            tig2barcode2premol2pos.entry(tig.to_string()).or_default().entry(barcode.to_string()).or_default().push((begin, end));
+        }
+    }
+
+    Ok(())
+}
+
+/* Molecules which can overlap region, molecules outside region are still possible */
+pub fn read_region(path: &str, index: &Index, region: &Region) -> Tig2Barcode2Premol2Pos {
+    let mut tig2barcode2premol2pos = Tig2Barcode2Premol2Pos::new();
+
+    let block = match index.contigs.get(&region.tig) {
+        Some(block) if !block.bins.is_empty() => block,
+        _ => return tig2barcode2premol2pos,
+    };
+
+    let first = (region.begin / index.bin) as usize;
+    let last = ((region.end.saturating_sub(1) / index.bin) as usize).min(block.bins.len() - 1);
+    if first > last {
+        return tig2barcode2premol2pos;
+    }
+
+    if let Some(start) = block.bins[first..=last].iter().flatten().min() {
+        let mut reader = BufReader::new(File::open(path).unwrap_or_else(|e| panic!("Can't open molecule table {}: {}", path, e)));
+        read_block(&mut reader, &mut tig2barcode2premol2pos, *start, block.end, region.end).expect("Error during molecule table parsing");
+    }
+
+    tig2barcode2premol2pos
+}
+
+/* Molecules of some contigs or of all contigs if none are given, with index only contigs selected are read */
+pub fn read_contigs(path: &str, contigs: &[String]) -> Tig2Barcode2Premol2Pos {
+    let index = match valid_index(path) {
+        Some(index) => index,
+        None => {
+            let mut tig2barcode2premol2pos = read(path);
+            if !contigs.is_empty() {
+                tig2barcode2premol2pos.retain(|tig, _| contigs.contains(tig));
+            }
+            return tig2barcode2premol2pos;
+        }
+    };
+
+    let mut tig2barcode2premol2pos = Tig2Barcode2Premol2Pos::new();
+    let mut reader = BufReader::new(File::open(path).unwrap_or_else(|e| panic!("Can't open molecule table {}: {}", path, e)));
+    for (tig, block) in index.contigs.iter() {
+        if contigs.is_empty() || contigs.contains(tig) {
+            read_block(&mut reader, &mut tig2barcode2premol2pos, block.start, block.end, u64::MAX).expect("Error during molecule table parsing");
+        }
+    }
+
+    tig2barcode2premol2pos
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn sorted_table() {
+        let mut tig2barcode2premol2pos = Tig2Barcode2Premol2Pos::new();
+        tig2barcode2premol2pos.entry("tig2".to_string()).or_default().insert("A".to_string(), vec![(10, 20)]);
+        let barcode2premol = tig2barcode2premol2pos.entry("tig1".to_string()).or_default();
+        barcode2premol.insert("B".to_string(), vec![(300, 500), (5, 50)]);
+        barcode2premol.insert("A".to_string(), vec![(100, 400)]);
+
+        let mut output = Vec::new();
+        write(&mut output, &tig2barcode2premol2pos).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "#contig\tbegin\tend\tbarcode\ntig1\t5\t50\tB\ntig1\t100\t400\tA\ntig1\t300\t500\tB\ntig2\t10\t20\tA\n");
+    }
+
+    #[test]
+    fn indexed_region() {
+        let path = std::env::temp_dir().join(format!("mapping2barcodegraph-table-{}.tsv", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        std::fs::write(&path, "#contig\tbegin\tend\tbarcode\ntig1\t0\t2500\tA\ntig1\t1200\t1800\tB\ntig1\t3100\t3900\tC\ntig2\t10\t20\tA\n").unwrap();
+
+        let index = index(&path, 1000).unwrap();
+        assert_eq!(index.contigs["tig1"].bins, vec![Some(26), Some(26), Some(26), Some(57)]);
+
+        let mut output = Vec::new();
+        write_index(&mut output, &index).unwrap();
+        let index_path = index_path(&path);
+        std::fs::write(&index_path, output).unwrap();
+        assert_eq!(read_index(&index_path), Ok(index.clone()));
+        assert_eq!(valid_index(&path), Some(index.clone()));
+
+        let molecules = read_region(&path, &index, &"tig1:3000-3500".parse().unwrap());
+        assert_eq!(molecules["tig1"].keys().collect::<Vec<_>>(), vec!["C"]);
+        let molecules = read_region(&path, &index, &"tig1:2000-2100".parse().unwrap());
+        assert!(molecules["tig1"].len() == 2 && molecules["tig1"].contains_key("A") && molecules["tig1"].contains_key("B"));
+        assert!(read_region(&path, &index, &"tig1:5000-6000".parse().unwrap()).is_empty());
+        assert_eq!(read_contigs(&path, &["tig2".to_string()]).keys().collect::<Vec<_>>(), vec!["tig2"]);
+
+        std::fs::write(&path, "tig1\t100\t200\tA\ntig1\t50\t200\tB\n").unwrap();
+        assert_eq!(valid_index(&path), None);
+        assert!(super::index(&path, 1000).is_err());
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(index_path).unwrap();
+    }
+}
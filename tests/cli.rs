@@ -0,0 +1,449 @@
+/* std use */
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::Once;
+
+fn data(name: &str) -> String {
+    format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/* Outputs of a run are in cargo target directory, outputs of previous run are remove at first call */
+fn output(name: &str) -> String {
+    static CLEAN: Once = Once::new();
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    CLEAN.call_once(|| {
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    });
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path: PathBuf = dir.join(name);
+    path.to_string_lossy().to_string()
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mapping2barcodegraph")).args(args).output().expect("binary can't be run")
+}
+
+/* Run with stdin fill by file content */
+fn run_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mapping2barcodegraph"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("binary can't be run");
+
+    child.stdin.take().unwrap().write_all(&std::fs::read(input).unwrap()).unwrap();
+    child.wait_with_output().expect("binary can't be run")
+}
+
+/* Build fixture graph with thresholds adapted to fixture, extra arguments are append */
+fn build(ema: &str, name: &str, extra: &[&str]) -> (Output, String) {
+    let path = output(name);
+
+    let mut args = vec!["build", "-e", ema, "-o", &path, "-l", "1000", "-p", "600"];
+    args.extend_from_slice(extra);
+
+    (run(&args), path)
+}
+
+fn attribute<'a>(line: &'a str, name: &str) -> &'a str {
+    let pattern = format!(" {}=\"", name);
+    let begin = line.find(&pattern).unwrap() + pattern.len();
+    let end = line[begin..].find('"').unwrap();
+
+    &line[begin..begin + end]
+}
+
+/* Graph without provenance, which record paths and parameters of run */
+fn graph(content: &str) -> String {
+    content.lines().filter(|l| !l.starts_with("<keywords>")).collect::<Vec<&str>>().join("\n")
+}
+
+fn nodes(path: &str) -> BTreeSet<String> {
+    std::fs::read_to_string(path).unwrap().lines().filter(|l| l.starts_with("<node ")).map(|l| attribute(l, "id").to_string()).collect()
+}
+
+fn edges(path: &str) -> BTreeSet<(String, String)> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|l| l.starts_with("<edge "))
+        .map(|l| (attribute(l, "source").to_string(), attribute(l, "target").to_string()))
+        .collect()
+}
+
+fn edge(a: &str, b: &str) -> (String, String) {
+    (a.to_string(), b.to_string())
+}
+
+/* F molecule end at the same position than B molecule so they aren't linked, A-C come from contig tigX absent from assembly */
+fn expected_edges() -> BTreeSet<(String, String)> {
+    vec![edge("AAAAAAAA", "BBBBBBBB"), edge("AAAAAAAA", "CCCCCCCC"), edge("CCCCCCCC", "DDDDDDDD")]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn build_fixture() {
+    let (result, path) = build(&data("ema.tsv"), "build.gexf", &["-a", &data("asm.fasta")]);
+    assert!(result.status.success());
+
+    // single read barcode E, barcodes of short contig tig3 and F without edge have no node, D-1 and D-2 are the same barcode
+    let expected_nodes: BTreeSet<String> = ["AAAAAAAA", "BBBBBBBB", "CCCCCCCC", "DDDDDDDD"].iter().map(|n| n.to_string()).collect();
+    assert_eq!(nodes(&path), expected_nodes);
+
+    assert_eq!(edges(&path), expected_edges());
+}
+
+/* Command line of version without subcommand still build a graph */
+#[test]
+fn build_without_subcommand() {
+    let (_, reference) = build(&data("ema.tsv"), "with_subcommand.gexf", &["-a", &data("asm.fasta")]);
+
+    let path = output("without_subcommand.gexf");
+    let result = run(&["-a", &data("asm.fasta"), "-e", &data("ema.tsv"), "-o", &path, "-l", "1000", "-p", "600"]);
+    assert!(result.status.success());
+    assert_eq!(std::fs::read(path).unwrap(), std::fs::read(reference).unwrap());
+}
+
+/* Two runs with same arguments write the same bytes, provenance included, even if outputs are write in other paths */
+#[test]
+fn same_bytes_for_same_run() {
+    let run_in = |name: &str| -> Vec<Vec<u8>> {
+        let summary = output(&format!("{}.tsv", name));
+        let clusters = output(&format!("{}.clusters.tsv", name));
+        let extra = ["-a", &data("asm.fasta"), "--contig-summary", &summary, "-c", "louvain", "--cluster-output", &clusters];
+
+        let (result, path) = build(&data("ema.tsv"), &format!("{}.gexf", name), &extra);
+        assert!(result.status.success());
+
+        [&path, &summary, &clusters].iter().map(|p| std::fs::read(p).unwrap()).collect()
+    };
+
+    let first = run_in("deterministic");
+    assert_eq!(run_in("deterministic"), first);
+    assert_eq!(run_in("other_deterministic"), first);
+}
+
+#[test]
+fn missing_contig() {
+    let (result, path) = build(&data("ema.tsv"), "skip.gexf", &["-a", &data("asm.fasta"), "--missing-contig", "skip"]);
+    assert!(result.status.success());
+    assert!(!edges(&path).contains(&edge("AAAAAAAA", "CCCCCCCC")));
+    assert!(edges(&path).contains(&edge("AAAAAAAA", "BBBBBBBB")));
+
+    let (result, _) = build(&data("ema.tsv"), "fail.gexf", &["-a", &data("asm.fasta"), "--missing-contig", "fail"]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("ERROR: 1 contigs present in alignment are absent from assembly: tigX"));
+}
+
+#[test]
+fn prune_log_removed() {
+    let (result, path) = build(&data("ema.tsv"), "prune.gexf", &["-a", &data("asm.fasta"), "--prune", "hub-degree:0", "-v"]);
+    assert!(result.status.success());
+
+    // every node with a degree upper than the minimal degree is removed
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    for node in ["AAAAAAAA", "CCCCCCCC"].iter() {
+        assert!(!nodes(&path).contains(*node));
+        assert!(stderr.contains(&format!("DEBUG: prune hub-degree:0: node {} removed", node)));
+    }
+    for (node1, node2) in expected_edges().difference(&edges(&path)) {
+        assert!(stderr.contains(&format!("DEBUG: prune hub-degree:0: edge {} {} removed", node1, node2)));
+    }
+    assert!(stderr.contains("INFO: prune hub-degree:0: 2 nodes and 3 edges removed"));
+}
+
+#[test]
+fn split_need_fasta() {
+    let fai = output("asm.fasta.fai");
+    std::fs::write(&fai, "tig1\t2000\t6\t60\t61\n").unwrap();
+
+    let result = run(&["breakpoints", "-e", &data("ema.tsv"), "-a", &fai, "-o", &output("breakpoints.bed"), "--split", &output("split.fasta")]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains(&format!("ERROR: split assembly need sequences, assembly {} must be a fasta", fai)));
+    assert!(!std::path::Path::new(&output("breakpoints.bed")).exists());
+}
+
+#[test]
+fn breakpoints_step_and_window_not_null() {
+    for option in &["--step", "--window"] {
+        let result = run(&["breakpoints", "-e", &data("ema.tsv"), "-o", &output("null.bed"), option, "0"]);
+        assert!(!result.status.success());
+        assert!(String::from_utf8_lossy(&result.stderr).contains("value must be upper or equal to 1"));
+    }
+}
+
+#[test]
+fn contig_summary() {
+    let summary = output("summary.tsv");
+    let (result, _) = build(&data("ema.tsv"), "summary.gexf", &["-a", &data("asm.fasta"), "--contig-summary", &summary]);
+    assert!(result.status.success());
+
+    let summary = std::fs::read_to_string(summary).unwrap();
+    assert!(summary.contains("tig1\t5000\tkept\t5\t5\t2\n"));
+    assert!(summary.contains("tig3\t300\tshort\t0\t0\t0\n"));
+    assert!(summary.contains("tigX\tNA\tmissing\t2\t2\t1\n"));
+
+    // contigs whose barcodes have only one read have no molecule but are summarised and reported
+    let ema = output("single_read.tsv");
+    std::fs::write(&ema, std::fs::read_to_string(data("ema.tsv")).unwrap() + "readZ\ttig2\t100\tZZZZZZZZ-1\t0\nreadY\ttigY\t100\tYYYYYYYY-1\t0\n").unwrap();
+    let summary = output("single_read_summary.tsv");
+    let (result, _) = build(&ema, "single_read.gexf", &["-a", &data("asm.fasta"), "--contig-summary", &summary]);
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("2 contigs present in alignment are absent from assembly: tigX, tigY"));
+
+    let summary = std::fs::read_to_string(summary).unwrap();
+    assert!(summary.contains("tig2\t4000\tkept\t0\t0\t0\n"));
+    assert!(summary.contains("tigY\tNA\tmissing\t0\t0\t0\n"));
+}
+
+#[test]
+fn same_graph_from_header_and_compressed_input() {
+    let (_, reference) = build(&data("ema.tsv"), "reference.gexf", &["-a", &data("asm.fasta")]);
+    let reference = graph(&std::fs::read_to_string(reference).unwrap());
+
+    let (result, header) = build(&data("ema.sq.tsv"), "header.gexf", &[]);
+    assert!(result.status.success());
+    assert_eq!(graph(&std::fs::read_to_string(header).unwrap()), reference);
+
+    let (result, compressed) = build(&data("ema.tsv.gz"), "compressed.gexf", &["-a", &data("asm.fasta")]);
+    assert!(result.status.success());
+    assert_eq!(graph(&std::fs::read_to_string(compressed).unwrap()), reference);
+}
+
+#[test]
+fn standard_input_and_output() {
+    let (_, reference) = build(&data("ema.tsv"), "std_reference.gexf", &["-a", &data("asm.fasta")]);
+    let reference = graph(&std::fs::read_to_string(reference).unwrap());
+
+    let result = run_stdin(&["build", "-e", "-", "-o", "-", "-a", &data("asm.fasta"), "-l", "1000", "-p", "600"], &data("ema.tsv.gz"));
+    assert!(result.status.success());
+    assert_eq!(graph(&String::from_utf8_lossy(&result.stdout)), reference);
+
+    let result = run_stdin(&["build", "-e", "-", "-e", "-", "-o", "-", "-a", &data("asm.fasta")], &data("ema.tsv"));
+    assert!(!result.status.success());
+}
+
+#[test]
+fn compressed_output() {
+    let (_, reference) = build(&data("ema.tsv"), "plain.gexf", &["-a", &data("asm.fasta")]);
+
+    for name in &["compressed.gexf.gz", "compressed.gexf.bz2", "compressed.gexf.xz", "compressed.gexf.zst"] {
+        let (result, path) = build(&data("ema.tsv"), name, &["-a", &data("asm.fasta")]);
+        assert!(result.status.success());
+        assert_ne!(std::fs::read(&path).unwrap(), std::fs::read(&reference).unwrap());
+
+        let stats = run(&["stats", "-i", &path]);
+        assert!(String::from_utf8_lossy(&stats.stdout).contains("summary\tedges\t3\n"));
+    }
+
+    let result = run_stdin(&["build", "-e", "-", "-o", "-", "--compress", "gzip", "-a", &data("asm.fasta"), "-l", "1000", "-p", "600"], &data("ema.tsv"));
+    assert!(result.status.success());
+    assert_eq!(&result.stdout[..2], &[0o037, 0o213]);
+}
+
+#[test]
+fn config_and_provenance() {
+    let config = output("run.toml");
+    std::fs::write(&config, format!("[build]\nema_info = \"{}\"\nasm = \"{}\"\noverlap-length = 1000\npremolecule-threshold = 600\n", data("ema.tsv"), data("asm.fasta"))).unwrap();
+
+    let (_, reference) = build(&data("ema.tsv"), "cli.gexf", &["-a", &data("asm.fasta")]);
+    let path = output("config.gexf");
+    let result = run(&["--config", &config, "build", "-o", &path]);
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("checksum inputs end"));
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(graph(&content), graph(&std::fs::read_to_string(reference).unwrap()));
+    assert!(content.contains("\"threshold\":[\"1000\"]"));
+    assert!(content.contains(&format!("\"{}\":{{\"size\":", data("ema.tsv"))));
+    assert!(content.contains("\"md5\":\""));
+    assert!(!content.contains(&path));
+
+    // command line override config
+    let result = run(&["--config", &config, "build", "-o", &path, "-l", "2000"]);
+    assert!(result.status.success());
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("\"threshold\":[\"2000\"]"));
+
+    let json = output("stats.json");
+    run(&["stats", "-i", &path, "-j", &json]);
+    assert!(std::fs::read_to_string(json).unwrap().contains("\"provenance\": {"));
+}
+
+#[test]
+fn yaml_config_and_global_options() {
+    let config = output("run.yaml");
+    std::fs::write(&config, format!("log-format: json\nquiet: 1\nbuild:\n  ema_info: {}\n  asm: {}\n  overlap-length: 1000\n  premolecule-threshold: 600\n", data("ema.tsv"), data("asm.fasta"))).unwrap();
+
+    let (_, reference) = build(&data("ema.tsv"), "yaml_reference.gexf", &["-a", &data("asm.fasta")]);
+    let path = output("yaml.gexf");
+    let result = run(&["--config", &config, "build", "-o", &path, "-v"]);
+    assert!(result.status.success());
+    assert_eq!(graph(&std::fs::read_to_string(&path).unwrap()), graph(&std::fs::read_to_string(reference).unwrap()));
+
+    // json log at info level, quiet of config is compensate by verbose of command line
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.lines().all(|line| line.starts_with('{')));
+    assert!(stderr.contains("\"level\":\"INFO\""));
+}
+
+#[test]
+fn checkpoint_resume() {
+    let dir = output("checkpoint");
+    let table = output("molecules.tsv");
+    let (result, first) = build(&data("ema.tsv"), "first_run.gexf", &["-a", &data("asm.fasta"), "--checkpoint-dir", &dir, "--molecule-table", &table]);
+    assert!(result.status.success());
+    assert!(std::fs::read_to_string(&table).unwrap().starts_with("#contig\tbegin\tend\tbarcode\ntig1\t"));
+
+    // same run, with another log level, resume from saved graph and write again molecule table
+    let path = output("first_run.gexf");
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&table).unwrap();
+    let result = run(&["build", "-e", &data("ema.tsv"), "-o", &path, "-l", "1000", "-p", "600", "-a", &data("asm.fasta"), "--checkpoint-dir", &dir, "--molecule-table", &table, "-v"]);
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("resume from checkpoint"));
+    assert!(!String::from_utf8_lossy(&result.stderr).contains("read ema info"));
+    assert_eq!(edges(&first), expected_edges());
+    assert!(std::fs::read_to_string(&table).unwrap().starts_with("#contig\tbegin\tend\tbarcode\ntig1\t"));
+
+    // same output with another weight model don't reuse checkpoint
+    let (result, _) = build(&data("ema.tsv"), "first_run.gexf", &["-a", &data("asm.fasta"), "--checkpoint-dir", &dir, "--molecule-table", &table, "--weight-model", "pairs"]);
+    assert!(result.status.success());
+    assert!(!String::from_utf8_lossy(&result.stderr).contains("resume from checkpoint"));
+}
+
+#[test]
+fn query_barcode_and_region() {
+    let table = output("query_molecules.tsv");
+    let (_, path) = build(&data("ema.tsv"), "query.gexf", &["-a", &data("asm.fasta"), "--molecule-table", &table]);
+
+    let subgraph = output("query_subgraph.gexf");
+    let result = run(&["query", "-g", &path, "-b", "AAAAAAAA", "-d", "1", "-s", &subgraph]);
+    assert!(result.status.success());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.starts_with("node\tdepth\tparent\toverlap\tweight\nAAAAAAAA\t0\tNA\t0\tNA\n"));
+    assert!(stdout.contains("BBBBBBBB\t1\tAAAAAAAA\t"));
+    assert!(!stdout.contains("DDDDDDDD"));
+    assert_eq!(nodes(&subgraph).len(), 3);
+
+    // same neighbours in table with overlap length of build
+    let nodes_of = |stdout: &[u8]| String::from_utf8_lossy(stdout).lines().skip(1).map(|l| l.split('\t').next().unwrap().to_string()).collect::<BTreeSet<String>>();
+    let from_table = run(&["query", "-m", &table, "-b", "AAAAAAAA", "-d", "2", "-l", "1000"]);
+    let from_graph = run(&["query", "-g", &path, "-b", "AAAAAAAA", "-d", "2"]);
+    assert_eq!(nodes_of(&from_table.stdout), nodes_of(&from_graph.stdout));
+
+    let result = run(&["query", "-m", &table, "-r", "tig1:2200-3100"]);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&result.stdout),
+        "node\tbarcode\tcontig\tbegin\tend\toverlap\nBBBBBBBB\tBBBBBBBB\ttig1\t300\t2300\t100\nFFFFFFFF\tFFFFFFFF\ttig1\t1200\t2300\t100\nCCCCCCCC\tCCCCCCCC\ttig1\t3000\t4500\t100\n"
+    );
+
+    // barcode graph nodes have no position
+    let result = run(&["query", "-g", &path, "-r", "tig2:0-500"]);
+    assert!(!result.status.success());
+}
+
+#[test]
+fn indexed_molecule_table() {
+    let table = output("indexed_molecules.tsv.gz");
+    let (_, path) = build(&data("ema.tsv"), "indexed.gexf", &["-a", &data("asm.fasta"), "--molecule-table", &table]);
+
+    // compressed table can't be indexed in place
+    assert!(!run(&["index", "-i", &table]).status.success());
+
+    let indexed = output("indexed_molecules.tsv");
+    let result = run(&["index", "-i", &table, "-o", &indexed, "-b", "1000"]);
+    assert!(result.status.success());
+    assert!(std::fs::read_to_string(format!("{}.idx", indexed)).unwrap().starts_with("#bin\t1000\tsize\t"));
+
+    let region = |table: &str| run(&["query", "-m", table, "-r", "tig1:2200-3100"]).stdout;
+    assert_eq!(region(&indexed), region(&table));
+
+    let from_table = output("indexed_from_table.gexf");
+    let result = run(&["build", "--molecules", &indexed, "-a", &data("asm.fasta"), "-l", "1000", "-o", &from_table]);
+    assert!(result.status.success());
+    assert_eq!(edges(&from_table), edges(&path));
+
+    let one_contig = output("indexed_one_contig.gexf");
+    let result = run(&["build", "--molecules", &indexed, "--contig", "tig2", "-a", &data("asm.fasta"), "-l", "1000", "-o", &one_contig]);
+    assert!(result.status.success());
+    assert!(edges(&one_contig).len() < edges(&path).len());
+}
+
+#[test]
+fn rewritten_indexed_table() {
+    let table = output("rewritten_molecules.tsv");
+    build(&data("ema.tsv"), "rewritten.gexf", &["-a", &data("asm.fasta"), "--molecule-table", &table]);
+    assert!(run(&["index", "-i", &table]).status.success());
+    let index = format!("{}.idx", table);
+
+    // a build which rewrite table remove its index
+    let libraries = format!("second={}", data("ema.tsv"));
+    build(&data("ema.tsv"), "rewritten.gexf", &["-e", &libraries, "-a", &data("asm.fasta"), "--molecule-table", &table]);
+    assert!(!std::path::Path::new(&index).exists());
+    let expected = run(&["query", "-m", &table, "-r", "tig1:2200-3100"]).stdout;
+    assert_eq!(String::from_utf8_lossy(&expected).lines().count(), 7);
+
+    // an index older than its table is ignored
+    let copy = output("rewritten_molecules_copy.tsv");
+    std::fs::copy(&table, &copy).unwrap();
+    build(&data("ema.tsv"), "rewritten.gexf", &["-a", &data("asm.fasta"), "--molecule-table", &table]);
+    assert!(run(&["index", "-i", &table]).status.success());
+    std::fs::copy(&copy, &table).unwrap();
+
+    let result = run(&["query", "-m", &table, "-r", "tig1:2200-3100"]);
+    assert!(result.status.success());
+    assert_eq!(result.stdout, expected);
+    assert!(String::from_utf8_lossy(&result.stderr).contains("modified after indexing"));
+}
+
+#[test]
+fn phased_vcf_split() {
+    let vcf = output("phased.vcf");
+    std::fs::write(&vcf, "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ntig1\t100\t.\tA\tC\t.\tPASS\t.\tGT:PS\t0|1:1\n").unwrap();
+
+    // every read carry allele of haplotype 1
+    let alleles = output("read_alleles.tsv");
+    let reads: String = std::fs::read_to_string(data("ema.tsv")).unwrap().lines().map(|l| format!("{}\ttig1\t100\tA\n", l.split('\t').next().unwrap())).collect();
+    std::fs::write(&alleles, reads).unwrap();
+
+    let summary = output("phased_contigs.tsv");
+    let (result, path) = build(&data("ema.tsv"), "phased.gexf", &["-a", &data("asm.fasta"), "--haplotype", "split", "--phased-vcf", &vcf, "--read-alleles", &alleles, "--contig-summary", &summary]);
+    assert!(result.status.success());
+
+    assert_eq!(edges(&output("phased.hp1.gexf")), expected_edges());
+    assert!(!std::path::Path::new(&path).exists());
+    assert!(!std::path::Path::new(&output("phased.hp2.gexf")).exists());
+    assert!(std::fs::read_to_string(output("phased_contigs.hp1.tsv")).unwrap().starts_with("contig\tlength\tstatus\tbarcodes\tmolecules\tedges\n"));
+}
+
+#[test]
+fn no_contig_length() {
+    let (result, _) = build(&data("ema.tsv"), "no_length.gexf", &[]);
+
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("contig length source (-a) is required"));
+}
+
+#[test]
+fn stats_of_fixture() {
+    let (_, path) = build(&data("ema.tsv"), "stats.gexf", &["-a", &data("asm.fasta")]);
+
+    let result = run(&["stats", "-i", &path]);
+    assert!(result.status.success());
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.contains("summary\tnodes\t4\n"));
+    assert!(stdout.contains("summary\tedges\t3\n"));
+    assert!(stdout.contains("summary\tcomponents\t1\n"));
+}